@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+# 从 nodejs/release-keys 仓库重新生成内置的 Node.js 发布团队公钥
+#
+# 用法: scripts/update-release-keys.sh [release-keys 仓库的本地目录]
+# 不指定目录时从 GitHub 下载；无法联网的机器上可以先在其他地方 clone 仓库再传入目录。
+set -euo pipefail
+
+REPO="https://raw.githubusercontent.com/nodejs/release-keys/main"
+LOCAL="${1:-}"
+OUT="$(cd "$(dirname "$0")/.." && pwd)/keys/nodejs-release-keys.asc"
+TMP="$(mktemp)"
+trap 'rm -f "$TMP"' EXIT
+
+fetch() {
+    if [ -n "$LOCAL" ]; then
+        cat "$LOCAL/$1"
+    else
+        curl -fsSL "$REPO/$1"
+    fi
+}
+
+# 保留文件头部的说明文字
+sed '/-----BEGIN PGP PUBLIC KEY BLOCK-----/,$d' "$OUT" > "$TMP"
+
+count=0
+while read -r fingerprint; do
+    [ -z "$fingerprint" ] && continue
+    echo "fetching $fingerprint" >&2
+    echo >> "$TMP"
+    fetch "keys/$fingerprint.asc" >> "$TMP"
+    count=$((count + 1))
+done < <(fetch keys.list)
+
+# 没有取到任何公钥时不覆盖原文件
+if [ "$count" -eq 0 ]; then
+    echo "no keys found in keys.list" >&2
+    exit 1
+fi
+
+mv "$TMP" "$OUT"
+trap - EXIT
+echo "updated $OUT with $count keys" >&2
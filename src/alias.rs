@@ -0,0 +1,90 @@
+use crate::error::{Result, RnmError};
+use crate::version::VersionSpec;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const ALIASES_DIR: &str = "aliases";
+
+// 没有其他地方指定版本时使用的别名
+pub const DEFAULT_ALIAS: &str = "default";
+
+// 别名可以指向另一个别名，限制展开层数防止循环
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// 用户定义的版本别名，每个别名保存为 `~/.rnm/aliases/<name>`，内容是版本描述
+pub struct AliasStore {
+    dir: PathBuf,
+}
+
+impl AliasStore {
+    pub fn new(rnm_dir: &Path) -> Self {
+        Self { dir: rnm_dir.join(ALIASES_DIR) }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        if !is_valid_name(name) {
+            return Ok(None);
+        }
+        let path = self.dir.join(name);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?.trim().to_string()))
+    }
+
+    /// 保存别名，名称不能与版本描述冲突，例如 `18`、`lts`、`latest`
+    pub fn set(&self, name: &str, spec: &str) -> Result<()> {
+        if !is_valid_name(name) || name.parse::<VersionSpec>().is_ok() {
+            return Err(RnmError::InvalidAlias(name.to_string()));
+        }
+        // 目标必须是合法的版本描述或已有的别名
+        self.expand(spec)?.parse::<VersionSpec>()?;
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(name), format!("{}\n", spec.trim()))?;
+        Ok(())
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        if self.get(name)?.is_none() {
+            return Err(RnmError::AliasNotFound(name.to_string()));
+        }
+        fs::remove_file(self.dir.join(name))?;
+        Ok(())
+    }
+
+    /// 所有别名及其版本描述，按名称排序
+    pub fn list(&self) -> Result<Vec<(String, String)>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut aliases = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(spec) = self.get(&name)? {
+                aliases.push((name, spec));
+            }
+        }
+        aliases.sort();
+        Ok(aliases)
+    }
+
+    /// 将别名展开为版本描述，不是别名时原样返回
+    pub fn expand(&self, spec: &str) -> Result<String> {
+        let mut current = spec.trim().to_string();
+        for _ in 0..MAX_ALIAS_DEPTH {
+            match self.get(&current)? {
+                Some(target) => current = target,
+                None => return Ok(current),
+            }
+        }
+        Err(RnmError::InvalidAlias(spec.to_string()))
+    }
+}
+
+// 别名就是文件名，不能包含路径分隔符或以 . 开头
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
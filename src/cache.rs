@@ -0,0 +1,151 @@
+use crate::checksum::sha256_file;
+use crate::config::get_cache_dir;
+use crate::error::{Result, RnmError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// 缓存中的一个安装包
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub filename: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+pub const INDEX_CACHE_FILE: &str = "index.json";
+
+/// 按内容寻址的安装包缓存，布局为 `~/.rnm/cache/<sha256>/<filename>`
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new() -> Result<Self> {
+        Ok(Self { dir: get_cache_dir()? })
+    }
+
+    fn entry_path(&self, filename: &str, sha256: &str) -> PathBuf {
+        self.dir.join(sha256.to_ascii_lowercase()).join(filename)
+    }
+
+    /// 查找缓存的安装包，内容与校验和不符的缓存会被删除
+    pub fn lookup(&self, filename: &str, sha256: &str) -> Result<Option<PathBuf>> {
+        let path = self.entry_path(filename, sha256);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        if !sha256_file(&path)?.eq_ignore_ascii_case(sha256) {
+            println!("缓存文件已损坏，重新下载: {}", path.display());
+            let _ = fs::remove_dir_all(path.parent().unwrap_or(&path));
+            return Ok(None);
+        }
+        Ok(Some(path))
+    }
+
+    /// 按文件名查找缓存的安装包，用于无法获取校验和的离线模式
+    pub fn find(&self, filename: &str) -> Result<Option<PathBuf>> {
+        for entry in self.entries()? {
+            if entry.filename == filename {
+                if let Some(path) = self.lookup(filename, &entry.sha256)? {
+                    return Ok(Some(path));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// 将已校验的安装包移入缓存，返回缓存中的路径
+    pub fn store(&self, src: &Path, filename: &str, sha256: &str) -> Result<PathBuf> {
+        let path = self.entry_path(filename, sha256);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // 临时目录与缓存在同一文件系统时可以直接重命名，否则复制
+        if fs::rename(src, &path).is_err() {
+            fs::copy(src, &path)?;
+            fs::remove_file(src)?;
+        }
+        Ok(path)
+    }
+
+    // 保存最近一次获取的 index.json，供离线模式使用
+    pub fn save_index(&self, body: &str) -> Result<()> {
+        fs::write(self.dir.join(INDEX_CACHE_FILE), body)?;
+        Ok(())
+    }
+
+    pub fn load_index(&self) -> Result<String> {
+        let path = self.dir.join(INDEX_CACHE_FILE);
+        if !path.is_file() {
+            return Err(RnmError::OfflineUnavailable(
+                "版本索引，请先在联网状态下运行一次 rnm-rs ls-remote".to_string(),
+            ));
+        }
+        Ok(fs::read_to_string(path)?)
+    }
+
+    /// 未下载完成的安装包路径，按校验和区分，换用其他镜像时也可以继续续传
+    pub fn partial_path(&self, filename: &str, sha256: &str) -> Result<PathBuf> {
+        let dir = self.dir.join(".partial");
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!("{}-{}.part", sha256.to_ascii_lowercase(), filename)))
+    }
+
+    // 在缓存目录中创建下载用的临时目录，保证下载完成后可以直接重命名进缓存
+    pub fn temp_dir(&self) -> Result<TempDir> {
+        Ok(tempfile::Builder::new().prefix(".download-").tempdir_in(&self.dir)?)
+    }
+
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for hash_dir in fs::read_dir(&self.dir)? {
+            let hash_dir = hash_dir?;
+            let sha256 = hash_dir.file_name().to_string_lossy().to_string();
+            // 跳过下载中的临时目录
+            if sha256.starts_with('.') || !hash_dir.file_type()?.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(hash_dir.path())? {
+                let file = file?;
+                let metadata = file.metadata()?;
+                if !metadata.is_file() {
+                    continue;
+                }
+                entries.push(CacheEntry {
+                    filename: file.file_name().to_string_lossy().to_string(),
+                    sha256: sha256.clone(),
+                    size: metadata.len(),
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.filename.cmp(&b.filename));
+        Ok(entries)
+    }
+
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.entries()?.iter().map(|e| e.size).sum())
+    }
+
+    /// 清空缓存，返回删除的安装包数量和释放的字节数
+    ///
+    /// 保留离线模式使用的 index.json。调用方需要持有锁，避免删除正在进行的下载。
+    pub fn clean(&self) -> Result<(usize, u64)> {
+        let entries = self.entries()?;
+        let freed = entries.iter().map(|e| e.size).sum();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_name() == INDEX_CACHE_FILE {
+                continue;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok((entries.len(), freed))
+    }
+}
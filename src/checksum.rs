@@ -0,0 +1,61 @@
+use crate::error::{Result, RnmError};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+pub const SHASUMS_FILE: &str = "SHASUMS256.txt";
+
+/// 从 SHASUMS256.txt 中查找指定文件的 SHA-256
+///
+/// 每行格式为 `<hex>  <filename>`。
+pub fn find_checksum(shasums: &str, filename: &str) -> Option<String> {
+    shasums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        (name == filename).then(|| hash.to_ascii_lowercase())
+    })
+}
+
+// 比较下载内容的摘要和预期值
+pub fn verify(filename: &str, expected: &str, actual: &str) -> Result<()> {
+    if !expected.eq_ignore_ascii_case(actual) {
+        return Err(RnmError::ChecksumMismatch {
+            file: filename.to_string(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn hex_digest(hasher: Sha256) -> String {
+    format!("{:x}", hasher.finalize())
+}
+
+// 复制文件的同时计算 SHA-256，用于 file:// 镜像
+pub fn copy_and_hash(src: &Path, dst: &Path) -> Result<String> {
+    let mut reader = File::open(src)?;
+    let mut writer = File::create(dst)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        writer.write_all(&buffer[..n])?;
+    }
+    writer.flush()?;
+    Ok(hex_digest(hasher))
+}
+
+// 计算已有文件的 SHA-256
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex_digest(hasher))
+}
@@ -1,10 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use anstyle::{AnsiColor, Color, Style};
-
-const ORANGE: &str = "\x1b[38;5;208m";
-const YELLOW: &str = "\x1b[1;38;5;226m";
-const PINK: &str = "\x1b[1;38;5;219m";
-const RESET: &str = "\x1b[0m";
+use crate::completions::{installed_versions, remote_versions};
+use crate::shell::Shell;
+use clap_complete::engine::ArgValueCandidates;
+use std::ffi::OsString;
+use std::path::PathBuf;
 
 const FULL_HELP_MESSAGE: &str = "For more information, visit: https://github.com/zhuima/rnm\n\
     \n\
@@ -24,10 +24,47 @@ const FULL_HELP_MESSAGE: &str = "For more information, visit: https://github.com
     styles = get_styles()
 )]
 pub struct Cli {
+    // 离线模式，只使用缓存的版本索引和安装包
+    #[arg(long, global = true, help = "离线模式，只使用缓存的版本索引和安装包")]
+    pub offline: bool,
+
+    // 下载镜像，优先级高于 RNM_NODE_MIRROR 环境变量和配置文件
+    #[arg(long, global = true, help = "Node.js 下载镜像地址，例如 https://npmmirror.com/mirrors/node")]
+    pub mirror: Option<String>,
+
+    // ls、ls-remote 和 current 的输出格式
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Plain, help = "ls、ls-remote 和 current 的输出格式")]
+    pub format: OutputFormat,
+
+    // 脚本中使用，等同于 --format json
+    #[arg(long, global = true, conflicts_with = "format", help = "以 JSON 格式输出，等同于 --format json")]
+    pub json: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+impl Cli {
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format
+        }
+    }
+}
+
+// 版本列表的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    // 适合阅读的格式，ls-remote 只显示最新的几个主版本
+    Plain,
+    // 每个版本一行，按列对齐
+    Table,
+    // 稳定的 JSON 格式，字段见 README
+    Json,
+}
+
 fn get_styles() -> clap::builder::Styles {
     clap::builder::Styles::styled()
         .header(Style::new().bold().underline().fg_color(Some(Color::Ansi(AnsiColor::Green))))
@@ -53,25 +90,216 @@ pub enum Commands {
     // 下载安装指定版本
     #[command(name = "install", alias = "i", about = "install specified version")]
     Install {
-        // 版本号
-        version: String,
+        // 版本号，省略时读取 .nvmrc、.node-version 或 package.json
+        #[arg(help = "版本号，省略时读取 .nvmrc、.node-version 或 package.json", add = ArgValueCandidates::new(remote_versions))]
+        version: Option<String>,
+
+        // 使用本地安装包，例如 ./node-v20.11.1-linux-x64.tar.gz
+        #[arg(long, value_name = "ARCHIVE", help = "使用本地安装包，例如 ./node-v20.11.1-linux-x64.tar.gz")]
+        from_file: Option<PathBuf>,
     },
 
     // 卸载指定版本
     #[command(name = "uninstall", alias = "un", about = "uninstall specified version")]
     Uninstall {
         // 版本号
+        #[arg(add = ArgValueCandidates::new(installed_versions))]
         version: String,
     },
 
     // 设置默认版本
     #[command(name = "use", alias = "u", about = "set default version")]
     Use {
-        // 版本号
-        version: String,
+        // 版本号，省略时读取 .nvmrc、.node-version 或 package.json
+        #[arg(help = "版本号，省略时读取 .nvmrc、.node-version 或 package.json", add = ArgValueCandidates::new(installed_versions))]
+        version: Option<String>,
+
+        // 根据项目声明自动选择满足要求的最新版本，必要时自动安装
+        #[arg(long, conflicts_with = "version", help = "根据项目声明自动选择满足要求的最新版本，未安装时自动安装")]
+        auto: bool,
+
+        // 在 /usr/local/bin 中创建链接，对所有用户生效，需要 sudo
+        #[arg(long, help = "在 /usr/local/bin 中创建链接，对所有用户生效，需要 sudo")]
+        system: bool,
+
+        // 没有满足要求的已安装版本时自动安装
+        #[arg(long, help = "没有满足要求的已安装版本时自动安装")]
+        install_if_missing: bool,
+
+        // 由 env --use-on-cd 生成的钩子使用，版本没有变化时不输出
+        #[arg(long, hide = true, conflicts_with_all = ["version", "auto", "system"])]
+        on_cd: bool,
     },
 
     // 查看当前版本
     #[command(name = "current", alias = "c", about = "show current version")]
     Current,
-}
\ No newline at end of file
+
+    // 使用指定版本执行命令，不改变当前版本
+    #[command(name = "exec", about = "run a command with a specific Node.js version")]
+    Exec {
+        // 版本号，省略时读取 .nvmrc、.node-version 或 package.json
+        #[arg(long, value_name = "VERSION", help = "版本号，省略时读取 .nvmrc、.node-version 或 package.json", add = ArgValueCandidates::new(installed_versions))]
+        using: Option<String>,
+
+        #[arg(long, help = "没有满足要求的已安装版本时自动安装")]
+        install_if_missing: bool,
+
+        // 要执行的命令及其参数，例如 -- npm test
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true, value_name = "COMMAND")]
+        command: Vec<OsString>,
+    },
+
+    // 使用指定版本的 node 运行脚本
+    #[command(name = "run", about = "run a script with a specific Node.js version")]
+    Run {
+        // 版本号
+        #[arg(add = ArgValueCandidates::new(installed_versions))]
+        version: String,
+
+        #[arg(long, help = "没有满足要求的已安装版本时自动安装")]
+        install_if_missing: bool,
+
+        // 脚本及其参数
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true, value_name = "SCRIPT")]
+        args: Vec<OsString>,
+    },
+
+    // 输出版本中可执行文件的路径
+    #[command(name = "which", about = "print the path of a Node.js binary for a version")]
+    Which {
+        // 版本号，省略时读取项目文件，没有项目文件时使用当前版本
+        #[arg(help = "版本号，省略时读取 .nvmrc、.node-version 或 package.json，再使用当前版本", add = ArgValueCandidates::new(installed_versions))]
+        version: Option<String>,
+
+        #[arg(long, value_enum, default_value_t = NodeBin::Node, help = "要查找的可执行文件")]
+        bin: NodeBin,
+    },
+
+    // 输出版本的安装目录
+    #[command(name = "where", about = "print the installation directory of a version")]
+    Where {
+        // 版本号，省略时读取项目文件，没有项目文件时使用当前版本
+        #[arg(help = "版本号，省略时读取 .nvmrc、.node-version 或 package.json，再使用当前版本", add = ArgValueCandidates::new(installed_versions))]
+        version: Option<String>,
+    },
+
+    // 输出设置当前 shell 环境的脚本，例如 eval "$(rnm-rs env)"
+    #[command(name = "env", about = "print shell setup for per-shell version switching")]
+    Env {
+        // 省略时根据 $SHELL 判断
+        #[arg(long, value_enum, help = "shell 类型，省略时根据 $SHELL 判断")]
+        shell: Option<Shell>,
+
+        // 进入目录时按 .nvmrc、.node-version 或 package.json 自动切换版本
+        #[arg(long, help = "进入目录时按 .nvmrc、.node-version 或 package.json 自动切换版本")]
+        use_on_cd: bool,
+
+        // 自动切换时安装缺少的版本
+        #[arg(long, requires = "use_on_cd", help = "自动切换时安装缺少的版本")]
+        install_if_missing: bool,
+    },
+
+    // 设置版本别名，default 别名在没有其他版本声明时使用
+    #[command(name = "alias", about = "create or update a version alias")]
+    Alias {
+        // 别名，例如 default 或 work
+        name: String,
+        // 版本号，例如 20、18.19 或 lts/iron
+        version: String,
+    },
+
+    // 删除版本别名
+    #[command(name = "unalias", about = "remove a version alias")]
+    Unalias {
+        name: String,
+    },
+
+    // 列出所有版本别名
+    #[command(name = "aliases", about = "list version aliases")]
+    Aliases,
+
+    // 重新生成 ~/.rnm/shims 中的 shim
+    #[command(name = "reshim", about = "regenerate shims for node, npm, npx, corepack and global package binaries")]
+    Reshim,
+
+    // 输出 shell 补全脚本
+    #[command(name = "completions", about = "print shell completion script")]
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    // 管理下载缓存 ~/.rnm/cache
+    #[command(name = "cache", about = "manage the download cache")]
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    // 读写配置文件 ~/.rnm/config.toml
+    #[command(name = "config", about = "get or set configuration")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+// which 可以查找的可执行文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NodeBin {
+    Node,
+    Npm,
+    Npx,
+}
+
+impl NodeBin {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Node => "node",
+            Self::Npm => "npm",
+            Self::Npx => "npx",
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    // 列出缓存的安装包
+    #[command(name = "ls", alias = "list", about = "list cached archives")]
+    Ls,
+
+    // 清空缓存
+    #[command(name = "clean", alias = "clear", about = "remove all cached archives")]
+    Clean,
+
+    // 查看缓存占用的空间
+    #[command(name = "size", about = "show total cache size")]
+    Size,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    // 列出所有配置项
+    #[command(name = "list", alias = "ls", about = "list all settings")]
+    List,
+
+    // 查看配置项
+    #[command(name = "get", about = "print a setting")]
+    Get {
+        key: String,
+    },
+
+    // 设置配置项
+    #[command(name = "set", about = "change a setting")]
+    Set {
+        key: String,
+        value: String,
+    },
+
+    // 删除配置项，恢复默认值
+    #[command(name = "unset", about = "reset a setting to its default")]
+    Unset {
+        key: String,
+    },
+}
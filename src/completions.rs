@@ -0,0 +1,83 @@
+use crate::cache::Cache;
+use crate::alias::AliasStore;
+use crate::config::{get_rnm_dir, VERSIONS_DIR};
+use crate::error::{Result, RnmError};
+use crate::lock::is_complete;
+use crate::remote::parse_index;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::Shells;
+use clap_complete::Shell;
+use semver::Version;
+use std::fs;
+use std::io;
+
+// 补全脚本回调 rnm-rs 时使用的环境变量
+pub const COMPLETE_ENV: &str = "COMPLETE";
+
+// 不依赖远程索引的版本描述
+const SPEC_KEYWORDS: [&str; 3] = ["latest", "lts", "lts/*"];
+
+/// 输出 shell 的补全脚本，脚本在补全时通过 COMPLETE 环境变量回调 rnm-rs
+pub fn write_registration(shell: Shell) -> Result<()> {
+    let name = shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .ok_or_else(|| RnmError::ConfigError(format!("不支持的 shell: {}", name)))?;
+    completer.write_registration(COMPLETE_ENV, "rnm-rs", "rnm-rs", "rnm-rs", &mut io::stdout())?;
+    Ok(())
+}
+
+/// use、uninstall 等命令的补全：已安装的版本和别名
+///
+/// 补全过程中输出的任何内容都会混进 shell 的候选项，这里直接读取版本目录，不经过 Manager，读取失败时不提供候选项。
+pub fn installed_versions() -> Vec<CompletionCandidate> {
+    let Ok(rnm_dir) = get_rnm_dir() else {
+        return Vec::new();
+    };
+
+    let mut versions: Vec<Version> = fs::read_dir(rnm_dir.join(VERSIONS_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| is_complete(&entry.path()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            Version::parse(name.strip_prefix("node-v")?).ok()
+        })
+        .collect();
+    versions.sort_by(|a, b| b.cmp(a));
+
+    let mut candidates: Vec<CompletionCandidate> =
+        versions.iter().map(|version| CompletionCandidate::new(version.to_string())).collect();
+    for (name, spec) in AliasStore::new(&rnm_dir).list().unwrap_or_default() {
+        candidates.push(CompletionCandidate::new(name).help(Some(format!("alias -> {}", spec).into())));
+    }
+    candidates
+}
+
+/// install 命令的补全：缓存的远程索引中的版本，不发起网络请求
+pub fn remote_versions() -> Vec<CompletionCandidate> {
+    let mut candidates: Vec<CompletionCandidate> = SPEC_KEYWORDS.iter().map(CompletionCandidate::new).collect();
+
+    let Ok(versions) = Cache::new().and_then(|cache| cache.load_index()).and_then(|index| parse_index(&index)) else {
+        return candidates;
+    };
+
+    let mut codenames: Vec<String> = Vec::new();
+    for remote in &versions {
+        let help = match &remote.lts {
+            Some(codename) => format!("LTS: {}  {}", codename, remote.date),
+            None => remote.date.clone(),
+        };
+        candidates.push(CompletionCandidate::new(remote.version.to_string()).help(Some(help.into())));
+        if let Some(codename) = &remote.lts {
+            let codename = format!("lts/{}", codename.to_ascii_lowercase());
+            if !codenames.contains(&codename) {
+                codenames.push(codename);
+            }
+        }
+    }
+    candidates.extend(codenames.into_iter().map(CompletionCandidate::new));
+    candidates
+}
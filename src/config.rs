@@ -1,10 +1,19 @@
 use crate::error::{RnmError, Result};
+use serde::{Deserialize, Serialize};
+use std::env;
 use std::path::PathBuf;
 use std::fs;
 
 
 pub const NODE_MIRROR: &str = "https://nodejs.org/dist";
-// pub const CURRENT_SYMLINK: &str = "current";
+pub const MIRROR_ENV: &str = "RNM_NODE_MIRROR";
+pub const CONFIG_FILE: &str = "config.toml";
+pub const VERSIONS_DIR: &str = "versions";
+// 当前 shell 会话的 multishell 链接，由 `rnm-rs env` 设置
+pub const MULTISHELL_ENV: &str = "RNM_MULTISHELL_PATH";
+pub const MULTISHELLS_DIR: &str = "multishells";
+// 用户级的当前版本链接 ~/.rnm/current，加入 PATH 后无需 sudo
+pub const CURRENT_SYMLINK: &str = "current";
 
 
 // 获取rnm的主目录， 默认为 ~/.rnm
@@ -18,7 +27,7 @@ pub fn get_rnm_dir() -> Result<PathBuf> {
 // 获取rnm的版本目录， 默认为 ~/.rnm/versions
 pub fn get_versions_dir() -> Result<PathBuf> {
     let rnm_dir = get_rnm_dir()?;
-    let versions_dir = rnm_dir.join("versions");
+    let versions_dir = rnm_dir.join(VERSIONS_DIR);
     
     // 确保目录存在
     if !versions_dir.exists() {
@@ -29,10 +38,234 @@ pub fn get_versions_dir() -> Result<PathBuf> {
 }
 
 
+// 获取下载缓存目录， 默认为 ~/.rnm/cache
+pub fn get_cache_dir() -> Result<PathBuf> {
+    let cache_dir = get_rnm_dir()?.join("cache");
 
+    // 确保目录存在
+    if !cache_dir.exists() {
+        fs::create_dir_all(&cache_dir)?;
+    }
+
+    Ok(cache_dir)
+}
+
+
+/// 持久化在 ~/.rnm/config.toml 中的用户配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Node.js 发布包镜像地址，例如 https://npmmirror.com/mirrors/node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<String>,
+
+    // 主镜像请求失败时依次尝试的备用镜像
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    // SHASUMS256.txt 的 OpenPGP 签名校验策略
+    pub signature: SignatureMode,
+
+    // 额外信任的公钥文件（armored），与内置的发布团队公钥一起使用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<PathBuf>,
+
+    // 下载中断时在同一镜像上的最大重试次数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_retries: Option<u32>,
+
+    // 离线模式：只使用缓存的版本索引和安装包
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub offline: bool,
+
+    // 安装包格式，Windows 上始终使用 zip
+    pub archive_format: ArchiveFormat,
+}
+
+pub const DEFAULT_DOWNLOAD_RETRIES: u32 = 5;
+
+// 可以通过 `rnm-rs config` 读写的配置项
+pub const SETTING_KEYS: [&str; 7] = [
+    "mirror",
+    "mirrors",
+    "signature",
+    "keyring",
+    "download_retries",
+    "offline",
+    "archive_format",
+];
+
+/// 安装包格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    // 优先使用体积更小的 .tar.xz，镜像没有提供时使用 .tar.gz
+    #[default]
+    Auto,
+    Xz,
+    Gz,
+}
+
+impl std::str::FromStr for ArchiveFormat {
+    type Err = RnmError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "xz" | "tar.xz" => Ok(Self::Xz),
+            "gz" | "tar.gz" => Ok(Self::Gz),
+            _ => Err(RnmError::ConfigError(format!("archive_format 只能是 auto、xz 或 gz，而不是 '{}'", s))),
+        }
+    }
+}
+
+impl std::fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Xz => write!(f, "xz"),
+            Self::Gz => write!(f, "gz"),
+        }
+    }
+}
 
+/// 签名校验策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureMode {
+    // 不校验签名
+    #[default]
+    Off,
+    // 校验失败时只打印警告
+    Warn,
+    // 校验失败时拒绝安装
+    Require,
+}
+
+impl std::str::FromStr for SignatureMode {
+    type Err = RnmError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "require" => Ok(Self::Require),
+            _ => Err(RnmError::ConfigError(format!("signature 只能是 off、warn 或 require，而不是 '{}'", s))),
+        }
+    }
+}
+
+impl std::fmt::Display for SignatureMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Warn => write!(f, "warn"),
+            Self::Require => write!(f, "require"),
+        }
+    }
+}
+
+impl Settings {
+    // 读取配置文件，文件不存在时使用默认配置
+    pub fn load() -> Result<Self> {
+        let path = get_config_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| RnmError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = get_config_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string_pretty(self).map_err(|e| RnmError::ConfigError(e.to_string()))?;
+        fs::write(&path, content)?;
+        Ok(())
+    }
 
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        match key {
+            "mirror" => Ok(self.mirror.clone()),
+            "mirrors" => Ok(Some(self.mirrors.join(",")).filter(|m| !m.is_empty())),
+            "signature" => Ok(Some(self.signature.to_string())),
+            "keyring" => Ok(self.keyring.as_ref().map(|p| p.display().to_string())),
+            "download_retries" => Ok(Some(self.download_retries().to_string())),
+            "offline" => Ok(Some(self.offline.to_string())),
+            "archive_format" => Ok(Some(self.archive_format.to_string())),
+            _ => Err(RnmError::ConfigError(format!("未知的配置项 '{}'", key))),
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: Option<String>) -> Result<()> {
+        match key {
+            "mirror" => self.mirror = value.map(|v| v.trim_end_matches('/').to_string()),
+            // 多个备用镜像以逗号分隔
+            "mirrors" => self.mirrors = value.as_deref().map(split_mirrors).unwrap_or_default(),
+            "signature" => self.signature = value.as_deref().map(str::parse).transpose()?.unwrap_or_default(),
+            "keyring" => self.keyring = value.map(PathBuf::from),
+            "download_retries" => {
+                self.download_retries = value
+                    .map(|v| v.trim().parse::<u32>())
+                    .transpose()
+                    .map_err(|e| RnmError::ConfigError(format!("download_retries 必须是非负整数: {}", e)))?
+            }
+            "offline" => {
+                self.offline = value
+                    .map(|v| v.trim().parse::<bool>())
+                    .transpose()
+                    .map_err(|_| RnmError::ConfigError("offline 只能是 true 或 false".to_string()))?
+                    .unwrap_or_default()
+            }
+            "archive_format" => self.archive_format = value.as_deref().map(str::parse).transpose()?.unwrap_or_default(),
+            _ => return Err(RnmError::ConfigError(format!("未知的配置项 '{}'", key))),
+        }
+        Ok(())
+    }
+
+    /// 应用本次运行的覆盖项，优先级为 命令行参数 > 环境变量 > 配置文件
+    pub fn with_overrides(mut self, mirror: Option<String>, offline: bool) -> Self {
+        if let Some(mirror) = mirror.or_else(|| env::var(MIRROR_ENV).ok().filter(|m| !m.is_empty())) {
+            self.mirror = Some(mirror);
+        }
+        self.offline |= offline;
+        self
+    }
+
+    pub fn download_retries(&self) -> u32 {
+        self.download_retries.unwrap_or(DEFAULT_DOWNLOAD_RETRIES)
+    }
+
+    /// 按顺序排列的镜像列表：主镜像（可以是逗号分隔的多个地址）在前，备用镜像在后
+    pub fn mirrors(&self) -> Vec<String> {
+        let mut mirrors: Vec<String> = Vec::new();
+        let primary = self.mirror.as_deref().map(split_mirrors).unwrap_or_default();
+        for mirror in primary.into_iter().chain(self.mirrors.iter().map(|m| m.trim_end_matches('/').to_string())) {
+            if !mirrors.contains(&mirror) {
+                mirrors.push(mirror);
+            }
+        }
+        if mirrors.is_empty() {
+            mirrors.push(NODE_MIRROR.to_string());
+        }
+        mirrors
+    }
+}
 
 
+fn split_mirrors(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|m| m.trim().trim_end_matches('/'))
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
 
+// 获取配置文件路径， 默认为 ~/.rnm/config.toml
+pub fn get_config_path() -> Result<PathBuf> {
+    Ok(get_rnm_dir()?.join(CONFIG_FILE))
+}
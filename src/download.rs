@@ -0,0 +1,336 @@
+use crate::checksum::hex_digest;
+use crate::error::{Result, RnmError};
+use crate::utils::StreamingExtractor;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::RandomState;
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// 重试间隔的初始值和上限
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// 下载到持久化的部分文件中，连接中断时用 Range 请求续传，返回完整内容的 SHA-256
+///
+/// `retries` 是单个地址上允许的重试次数，每次重试之间按指数退避并加入随机抖动。
+/// 传入 `extractor` 时，下载到的数据会同时送入后台线程边下载边解压。
+pub async fn download_resumable(
+    client: &Client,
+    url: &str,
+    partial: &Path,
+    retries: u32,
+    mut extractor: Option<&mut StreamingExtractor>,
+) -> Result<String> {
+    let pb = ProgressBar::new(0);
+    pb.set_style(ProgressStyle::default_bar()
+    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+    .unwrap()
+    .progress_chars("#>-"));
+    // 与解压进度条一起显示
+    let pb = match extractor.as_deref() {
+        Some(extractor) => extractor.add_progress(pb),
+        None => pb,
+    };
+
+    let mut attempt = 0;
+    let digest = loop {
+        match download_once(client, url, partial, &pb, extractor.as_deref_mut()).await {
+            Ok(digest) => break digest,
+            Err(e) if attempt < retries && e.is_transient() => {
+                attempt += 1;
+                let delay = backoff(attempt);
+                pb.println(format!(
+                    "下载中断: {}，{:.1} 秒后进行第 {}/{} 次重试",
+                    e,
+                    delay.as_secs_f64(),
+                    attempt,
+                    retries
+                ));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                pb.abandon();
+                return Err(e);
+            }
+        }
+    };
+
+    pb.finish_with_message("Download complete");
+    Ok(digest)
+}
+
+// 发起一次请求，已有部分内容时从断点处继续，返回完整文件的 SHA-256
+async fn download_once(
+    client: &Client,
+    url: &str,
+    partial: &Path,
+    pb: &ProgressBar,
+    mut extractor: Option<&mut StreamingExtractor>,
+) -> Result<String> {
+    let mut file = OpenOptions::new().create(true).truncate(false).write(true).open(partial)?;
+    let existing = file.seek(SeekFrom::End(0))?;
+
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(RANGE, format!("bytes={}-", existing));
+    }
+    let mut response = request.send().await?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // 部分文件已经完整
+        let total = content_range_total(&response);
+        if total == Some(existing) {
+            pb.set_length(existing);
+            pb.set_position(existing);
+            if let Some(extractor) = extractor {
+                extractor.set_length(existing);
+                feed_partial(extractor, partial, existing)?;
+            }
+            return sha256_prefix(partial, existing).map(hex_digest);
+        }
+        // 与服务器上的文件不一致，清空后重新下载
+        file.set_len(0)?;
+        return Err(RnmError::TruncatedDownload { expected: total.unwrap_or(0), actual: existing });
+    }
+    if let Err(e) = response.error_for_status_ref() {
+        return Err(e.into());
+    }
+
+    // 服务器忽略 Range 返回完整内容时，丢弃已有内容重新写入
+    let resumed = existing > 0
+        && response.status() == StatusCode::PARTIAL_CONTENT
+        && content_range_start(&response) == Some(existing);
+    // 续传时先计算已有内容的摘要，新内容边下载边计算
+    let (offset, mut hasher) = if resumed {
+        (existing, sha256_prefix(partial, existing)?)
+    } else {
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        (0, Sha256::new())
+    };
+
+    let expected = response.content_length().map(|len| offset + len);
+    pb.set_length(expected.unwrap_or(0));
+    pb.set_position(offset);
+    if let Some(extractor) = extractor.as_deref_mut() {
+        extractor.set_length(expected.unwrap_or(0));
+        feed_partial(extractor, partial, offset)?;
+    }
+
+    let mut received = offset;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+        hasher.update(&chunk);
+        if let Some(extractor) = extractor.as_deref_mut() {
+            extractor.feed(received, &chunk);
+        }
+        received += chunk.len() as u64;
+        pb.set_position(received);
+    }
+    file.flush()?;
+
+    // 连接提前关闭时响应体会比 Content-Length 短
+    if let Some(expected) = expected {
+        if received != expected {
+            return Err(RnmError::TruncatedDownload { expected, actual: received });
+        }
+    }
+    Ok(hex_digest(hasher))
+}
+
+// 计算部分文件前 len 个字节的摘要
+fn sha256_prefix(path: &Path, len: u64) -> Result<Sha256> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?.take(len), &mut hasher)?;
+    Ok(hasher)
+}
+
+// 续传时把部分文件中还没有送入解压线程的内容补上
+fn feed_partial(extractor: &mut StreamingExtractor, path: &Path, len: u64) -> Result<()> {
+    let fed = extractor.fed();
+    if fed >= len {
+        return Ok(());
+    }
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(fed))?;
+    let mut reader = file.take(len - fed);
+    let mut buffer = vec![0; 64 * 1024];
+    let mut offset = fed;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        extractor.feed(offset, &buffer[..n]);
+        offset += n as u64;
+    }
+}
+
+// 解析 `Content-Range: bytes <start>-<end>/<total>` 中的起始位置
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
+// 解析 `Content-Range: bytes */<total>` 中的总长度
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    value.rsplit('/').next()?.trim().parse().ok()
+}
+
+// 指数退避加随机抖动: min(base * 2^(n-1), max) + [0, base)
+fn backoff(attempt: u32) -> Duration {
+    let exp = BACKOFF_BASE.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    let jitter = RandomState::new().build_hasher().finish() % BACKOFF_BASE.as_millis() as u64;
+    exp.min(BACKOFF_MAX) + Duration::from_millis(jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const BODY_LEN: usize = 64 * 1024;
+
+    fn body() -> Vec<u8> {
+        (0..BODY_LEN).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    fn expected_digest() -> String {
+        hex_digest(Sha256::new().chain_update(body()))
+    }
+
+    // 本地 HTTP 服务：第一次请求只发送 cut_first 个字节后断开，ignore_range 时总是返回完整内容
+    struct Server {
+        url: String,
+        requests: Arc<AtomicUsize>,
+        ranges: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    async fn serve(cut_first: Option<usize>, ignore_range: bool) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/node.tar.gz", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+
+        let (count, seen) = (requests.clone(), ranges.clone());
+        tokio::spawn(async move {
+            let body = body();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut head = Vec::new();
+                let mut buf = [0; 1024];
+                while !head.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    head.extend_from_slice(&buf[..n]);
+                }
+                let head = String::from_utf8_lossy(&head).to_string();
+                let range = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: ").or_else(|| line.strip_prefix("Range: ")))
+                    .map(str::to_string);
+                let index = count.fetch_add(1, Ordering::SeqCst);
+                seen.lock().unwrap().push(range.clone());
+
+                let start = range
+                    .filter(|_| !ignore_range)
+                    .and_then(|r| r.strip_prefix("bytes=")?.trim_end_matches('-').parse::<usize>().ok());
+                let (status, extra, content) = match start {
+                    Some(start) if start >= body.len() => {
+                        ("416 Range Not Satisfiable", format!("Content-Range: bytes */{}\r\n", body.len()), &body[..0])
+                    }
+                    Some(start) => (
+                        "206 Partial Content",
+                        format!("Content-Range: bytes {}-{}/{}\r\n", start, body.len() - 1, body.len()),
+                        &body[start..],
+                    ),
+                    None => ("200 OK", String::new(), &body[..]),
+                };
+                let header = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                    status,
+                    content.len(),
+                    extra
+                );
+                socket.write_all(header.as_bytes()).await.unwrap();
+                let sent = match cut_first {
+                    Some(cut) if index == 0 => &content[..cut],
+                    _ => content,
+                };
+                let _ = socket.write_all(sent).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        Server { url, requests, ranges }
+    }
+
+    #[tokio::test]
+    async fn resumes_truncated_body_with_range() {
+        let server = serve(Some(20_000), false).await;
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("node.tar.gz");
+
+        let digest = download_resumable(&Client::new(), &server.url, &partial, 3, None).await.unwrap();
+
+        assert_eq!(digest, expected_digest());
+        assert_eq!(std::fs::read(&partial).unwrap(), body());
+        assert_eq!(server.requests.load(Ordering::SeqCst), 2);
+        assert_eq!(server.ranges.lock().unwrap()[1].as_deref(), Some("bytes=20000-"));
+    }
+
+    #[tokio::test]
+    async fn restarts_when_server_ignores_range() {
+        let server = serve(Some(20_000), true).await;
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("node.tar.gz");
+
+        let digest = download_resumable(&Client::new(), &server.url, &partial, 3, None).await.unwrap();
+
+        // 第二次请求带了 Range 但收到 200，已有内容被丢弃而不是重复追加
+        assert_eq!(digest, expected_digest());
+        assert_eq!(std::fs::read(&partial).unwrap(), body());
+        assert_eq!(server.requests.load(Ordering::SeqCst), 2);
+        assert_eq!(server.ranges.lock().unwrap()[1].as_deref(), Some("bytes=20000-"));
+    }
+
+    #[tokio::test]
+    async fn complete_partial_file_gets_416() {
+        let server = serve(None, false).await;
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("node.tar.gz");
+        std::fs::write(&partial, body()).unwrap();
+
+        let digest = download_resumable(&Client::new(), &server.url, &partial, 0, None).await.unwrap();
+
+        assert_eq!(digest, expected_digest());
+        assert_eq!(server.requests.load(Ordering::SeqCst), 1);
+        assert_eq!(server.ranges.lock().unwrap()[0].as_deref(), Some(&*format!("bytes={}-", BODY_LEN)));
+    }
+
+    #[tokio::test]
+    async fn truncated_body_without_retries_keeps_partial() {
+        let server = serve(Some(20_000), false).await;
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("node.tar.gz");
+
+        let result = download_resumable(&Client::new(), &server.url, &partial, 0, None).await;
+
+        assert!(result.as_ref().is_err_and(|e| e.is_transient()), "{:?}", result);
+        assert_eq!(server.requests.load(Ordering::SeqCst), 1);
+        // 已收到的内容留在部分文件中，下次安装时续传
+        assert_eq!(std::fs::read(&partial).unwrap(), &body()[..20_000]);
+    }
+}
@@ -8,20 +8,56 @@ pub enum RnmError {
     #[error("文件 I/O 错误: {0}")]
     FileIoError(#[from] std::io::Error),
 
+    #[error("下载内容不完整: 预期 {expected} 字节，实际收到 {actual} 字节")]
+    TruncatedDownload { expected: u64, actual: u64 },
+
+    #[error("{file} 校验失败: 预期 SHA-256 为 {expected}，实际为 {actual}")]
+    ChecksumMismatch { file: String, expected: String, actual: String },
+
+    #[error("SHASUMS256.txt 中没有 {0} 的校验和")]
+    ChecksumMissing(String),
+
+    #[error("SHASUMS256.txt 签名校验失败: {0}")]
+    SignatureVerificationFailed(String),
+
+    #[error("离线模式下无法获取 {0}")]
+    OfflineUnavailable(String),
+
+    #[error("所有镜像均请求失败，最后一个错误: {0}")]
+    AllMirrorsFailed(String),
+
     #[error("解压 .tar.gz 文件失败: {0}")]
     TarGzFileError(String),
 
+    #[error("解压 .tar.xz 文件失败: {0}")]
+    TarXzFileError(String),
+
     #[error("解压 .zip 文件失败: {0}")]
     ZipFileError(String),
 
+    #[error("安装包中包含不安全的路径: {0}")]
+    UnsafeArchiveEntry(String),
+
+    #[error("配置错误: {0}")]
+    ConfigError(String),
+
     #[error("无法获取用户 Home 目录: {0}")]
     HomeDirError(String),
 
     #[error("无效的 Node.js 版本: {0}")]
     InvalidNodeVersion(String),
 
-    #[error("HTML 解析失败: {0}")]
-    HtmlParseError(String),
+    #[error("版本索引解析失败: {0}")]
+    IndexParseError(String),
+
+    #[error("JSON 输出失败: {0}")]
+    JsonOutputError(String),
+
+    #[error("未找到版本 '{0}'")]
+    VersionNotFound(String),
+
+    #[error("版本 '{0}' 没有提供 {1} 平台的安装包")]
+    PlatformUnavailable(String, String),
 
     #[error("不支持的平台: os: {0}, arch: {1}")]
     UnsupportedPlatform(String, String),
@@ -34,6 +70,46 @@ pub enum RnmError {
 
     #[error("版本 '{0}' 未安装")]
     NotInstalled(String),
+
+    #[error("未指定版本，且在 {0} 及其上级目录中未找到 .nvmrc、.node-version 或 package.json 中的 Node.js 版本声明")]
+    NoProjectVersion(String),
+
+    #[error("没有选择 Node.js 版本，请设置 RNM_NODE_VERSION、在项目中添加 .nvmrc 或运行 rnm-rs use")]
+    NoVersionSelected,
+
+    #[error("别名 '{0}' 无效: 只能包含字母、数字、-、_ 和 .，不能与版本描述相同，也不能循环引用")]
+    InvalidAlias(String),
+
+    #[error("别名 '{0}' 不存在")]
+    AliasNotFound(String),
+
+    #[error("{1} 中没有命令 {0}")]
+    CommandNotFound(String, String),
+}
+
+impl RnmError {
+    /// 是否为可以重试的临时错误：连接失败、超时、5xx 或响应体不完整
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RnmError::NetworkRequestError(e) => {
+                e.is_connect()
+                    || e.is_timeout()
+                    || e.is_body()
+                    || e.is_request()
+                    || e.status().is_some_and(|s| s.is_server_error())
+            }
+            RnmError::TruncatedDownload { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// 是否应该换用下一个镜像重试：临时错误，或 file:// 镜像中缺少文件
+    pub fn is_mirror_failure(&self) -> bool {
+        match self {
+            RnmError::FileIoError(e) => e.kind() == std::io::ErrorKind::NotFound,
+            _ => self.is_transient(),
+        }
+    }
 }
 
 // 定义一个统一的Result类型
@@ -0,0 +1,59 @@
+use crate::error::Result;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::Path;
+
+pub const LOCK_FILE: &str = ".lock";
+
+// 安装过程中使用的暂存目录前缀，与版本目录位于同一文件系统
+pub const STAGING_PREFIX: &str = ".staging-";
+
+// 安装完成后写入版本目录的标记文件，没有该文件的目录视为未完成的安装
+pub const COMPLETE_MARKER: &str = ".rnm-complete";
+
+// ~/.rnm 中的标记文件，表示已经为引入完成标记之前安装的版本补写过标记
+pub const MARKERS_MIGRATED: &str = ".markers-migrated";
+
+/// `~/.rnm` 上的咨询式文件锁，防止多个 rnm-rs 进程同时修改版本目录
+///
+/// 锁在对象被丢弃、文件关闭时自动释放，进程异常退出时也由操作系统释放。
+pub struct RnmLock {
+    _file: File,
+}
+
+impl RnmLock {
+    pub fn acquire(rnm_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(rnm_dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(rnm_dir.join(LOCK_FILE))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                eprintln!("另一个 rnm-rs 进程正在修改 {}，等待其完成...", rnm_dir.display());
+                file.lock()?;
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+/// 版本目录中是否有完成标记
+pub fn is_complete(version_path: &Path) -> bool {
+    version_path.join(COMPLETE_MARKER).is_file()
+}
+
+/// 删除上次运行中断后遗留的暂存目录，调用方需要持有锁
+pub fn clean_stale_staging(versions_dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(versions_dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(STAGING_PREFIX) {
+            eprintln!("清理未完成安装的暂存目录: {}", entry.path().display());
+            fs::remove_dir_all(entry.path())?;
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,51 @@
+mod alias;
+mod cache;
+mod checksum;
 mod cli;
+mod completions;
 mod config;
+mod download;
 mod error;
+mod lock;
 mod manager;
+mod mirror;
+mod output;
+mod project;
+mod remote;
+mod shell;
+mod shim;
+mod signature;
 mod utils;
+mod version;
 
-use clap::Parser;
-use cli::{Cli, Commands};
-use error::Result;
-use manager::Manager;
+use clap::{CommandFactory, Parser};
+use clap_complete::env::CompleteEnv;
+use cache::Cache;
+use cli::{CacheAction, Cli, Commands, ConfigAction, OutputFormat};
+use indicatif::HumanBytes;
+use lock::RnmLock;
+use config::{get_rnm_dir, Settings, MULTISHELL_ENV, SETTING_KEYS};
+use error::{Result, RnmError};
+use manager::{node_bin_dir, Manager};
+use shell::Shell;
+use utils::exec_in_path;
 
 #[tokio::main]
 async fn main() {
+    // 以 node、npm 等 shim 的名字被调用时，转交给对应版本中的命令
+    if let Some(name) = shim::invoked_as() {
+        match shim::run(&name).await {
+            Ok(code) => std::process::exit(code),
+            Err(e) => {
+                eprintln!("rnm-rs: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // 补全脚本通过 COMPLETE 环境变量回调时，输出候选项后直接退出
+    CompleteEnv::with_factory(Cli::command).var(completions::COMPLETE_ENV).complete();
+
     if let Err(e) = run().await {
         eprintln!("{}", e);
         std::process::exit(1);
@@ -19,50 +54,188 @@ async fn main() {
 
 async fn run() -> Result<()> {
     let cli = Cli::parse();
-    let manager = Manager::new()?;
+    let format = cli.output_format();
+
+    if let Commands::Completions { shell } = cli.command {
+        return completions::write_registration(shell);
+    }
+
+    // 配置命令只操作配置文件，不应用本次运行的覆盖项
+    if let Commands::Config { action } = cli.command {
+        return run_config(action);
+    }
+    if let Commands::Cache { action } = cli.command {
+        return run_cache(action);
+    }
+
+    let settings = Settings::load()?.with_overrides(cli.mirror, cli.offline);
+    let manager = Manager::new(&settings)?;
     match cli.command {
         Commands::LsRemote { version } => {
-            let versions = manager.list_remote(version.as_deref()).await?;
-            println!("\n可用的 Node.js 版本:");
-            for version in versions {
-                println!("{}", version);
-            }
-            if version.is_none() {
-                println!("\n提示: 使用 `rnm-rs ls-remote <version>` 查看特定版本");
-                println!("例如: rnm-rs ls-remote 18 或 rnm-rs ls-remote 18.15\n");
+            if format == OutputFormat::Plain {
+                println!("正在获取版本信息...");
             }
+            let versions = manager.list_remote(version.as_deref()).await?;
+            output::print_remote(&versions, version.as_deref(), format)?;
         }
         Commands::Ls => {
             let versions = manager.list_local().await?;
-            println!("Installed Node.js versions:");
-            if versions.is_empty() {
-                println!("No versions installed");
-                return Ok(());
-            }
-            
-            // 直接显示版本列表
-            for version in versions {
-                println!("  {}", version);
-            }
+            output::print_local(&versions, format)?;
         }
-        Commands::Install { version } => {
-            manager.install(&version).await?;
+        Commands::Install { version, from_file } => {
+            if let Some(archive) = from_file {
+                manager.install_from_file(&archive, version.as_deref())?;
+            } else {
+                let version = project::version_or_project(version)?;
+                manager.install(&version).await?;
+            }
         }
         Commands::Uninstall { version } => {
             manager.uninstall(&version).await?;
         }
-        Commands::Use { version } => {
-            manager.use_version(&version)?;
+        Commands::Use { version, auto, system, install_if_missing, on_cd } => {
+            if on_cd {
+                manager.use_on_cd(install_if_missing).await?;
+            } else if auto {
+                let project = project::project_version()?;
+                manager.use_best_match(&project.spec, system).await?;
+            } else if install_if_missing {
+                let version = project::version_or_project(version)?;
+                manager.use_best_match(&version, system).await?;
+            } else {
+                let version = project::version_or_project(version)?;
+                manager.use_version(&version, system).await?;
+            }
         }
         Commands::Current => {
-            match manager.current().await? {
-                Some(version) => println!("Current active version: {}", version),
-                None => println!("No version is currently active through rnm"),
+            let version = manager.current_info().await?;
+            output::print_current(version.as_ref(), format)?;
+        }
+        Commands::Env { shell, use_on_cd, install_if_missing } => {
+            // 输出的内容会被 shell 执行，只能打印脚本本身
+            let shell = shell.unwrap_or_else(Shell::detect);
+            let multishell = manager.create_multishell().await?;
+            println!("{}", shell.set_env(MULTISHELL_ENV, &multishell.display().to_string()));
+            println!("{}", shell.prepend_path(&node_bin_dir(&multishell).display().to_string()));
+            if use_on_cd {
+                let command = if install_if_missing { "rnm-rs use --on-cd --install-if-missing" } else { "rnm-rs use --on-cd" };
+                println!("{}", shell.use_on_cd_hook(command));
+            }
+        }
+        Commands::Exec { using, install_if_missing, command } => {
+            let version = project::version_or_project(using)?;
+            let version_path = manager.exec_version(&version, install_if_missing).await?;
+            let code = exec_in_path(&command[0], &command[1..], &node_bin_dir(&version_path))?;
+            std::process::exit(code);
+        }
+        Commands::Run { version, install_if_missing, args } => {
+            let version_path = manager.exec_version(&version, install_if_missing).await?;
+            let bin_dir = node_bin_dir(&version_path);
+            let node = shim::find_executable(&bin_dir, "node").ok_or_else(|| {
+                RnmError::CommandNotFound("node".to_string(), version_path.display().to_string())
+            })?;
+            let code = exec_in_path(node.as_os_str(), &args, &bin_dir)?;
+            std::process::exit(code);
+        }
+        Commands::Which { version, bin } => {
+            let version_path = manager.installed_path(project_or_current(version)?.as_deref()).await?;
+            let bin_dir = node_bin_dir(&version_path);
+            let path = shim::find_executable(&bin_dir, bin.name()).ok_or_else(|| {
+                RnmError::CommandNotFound(bin.name().to_string(), version_path.display().to_string())
+            })?;
+            println!("{}", path.display());
+        }
+        Commands::Where { version } => {
+            let version_path = manager.installed_path(project_or_current(version)?.as_deref()).await?;
+            println!("{}", version_path.display());
+        }
+        Commands::Alias { name, version } => {
+            manager.set_alias(&name, &version)?;
+            println!("{} -> {}", name, version);
+        }
+        Commands::Unalias { name } => {
+            manager.remove_alias(&name)?;
+            println!("已删除别名 {}", name);
+        }
+        Commands::Aliases => {
+            let aliases = manager.list_aliases().await?;
+            if aliases.is_empty() {
+                println!("No aliases defined");
             }
+            for (name, spec, resolved) in aliases {
+                match resolved {
+                    Some(version) if version != spec => println!("{} -> {} ({})", name, spec, version),
+                    Some(_) => println!("{} -> {}", name, spec),
+                    None => println!("{} -> {} (not installed)", name, spec),
+                }
+            }
+        }
+        Commands::Reshim => {
+            let (dir, count) = manager.reshim()?;
+            println!("已在 {} 中生成 {} 个 shim", dir.display(), count);
+            println!("提示: 将 {} 加入 PATH 后，node、npm 等命令会按项目自动选择版本", dir.display());
         }
+        Commands::Config { .. } | Commands::Cache { .. } | Commands::Completions { .. } => unreachable!(),
     }
     Ok(())
 }
 
+// 未指定版本时使用项目文件中的版本，都没有时返回 None 表示使用当前版本
+fn project_or_current(version: Option<String>) -> Result<Option<String>> {
+    if version.is_some() {
+        return Ok(version);
+    }
+    let project = project::find_project_version(&std::env::current_dir()?)?;
+    Ok(project.map(|project| project.spec))
+}
 
+fn run_config(action: ConfigAction) -> Result<()> {
+    let mut settings = Settings::load()?;
+    match action {
+        ConfigAction::List => {
+            for key in SETTING_KEYS {
+                let value = settings.get(key)?.unwrap_or_default();
+                println!("{} = {}", key, value);
+            }
+        }
+        ConfigAction::Get { key } => {
+            if let Some(value) = settings.get(&key)? {
+                println!("{}", value);
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            settings.set(&key, Some(value))?;
+            settings.save()?;
+        }
+        ConfigAction::Unset { key } => {
+            settings.set(&key, None)?;
+            settings.save()?;
+        }
+    }
+    Ok(())
+}
 
+fn run_cache(action: CacheAction) -> Result<()> {
+    let cache = Cache::new()?;
+    match action {
+        CacheAction::Ls => {
+            let entries = cache.entries()?;
+            if entries.is_empty() {
+                println!("Download cache is empty");
+            }
+            for entry in entries {
+                println!("{}  {:>10}  {}", &entry.sha256[..12.min(entry.sha256.len())], HumanBytes(entry.size), entry.filename);
+            }
+        }
+        CacheAction::Clean => {
+            // 与安装互斥，避免删除正在下载或解压的文件
+            let _lock = RnmLock::acquire(&get_rnm_dir()?)?;
+            let (count, freed) = cache.clean()?;
+            println!("Removed {} cached archives, freed {}", count, HumanBytes(freed));
+        }
+        CacheAction::Size => {
+            println!("{}", HumanBytes(cache.size()?));
+        }
+    }
+    Ok(())
+}
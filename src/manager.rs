@@ -1,14 +1,31 @@
-use crate::config::{get_versions_dir, NODE_MIRROR};
+use crate::alias::{AliasStore, DEFAULT_ALIAS};
+use crate::cache::Cache;
+use crate::checksum::{copy_and_hash, find_checksum, sha256_file, verify, SHASUMS_FILE};
+use crate::config::{get_versions_dir, ArchiveFormat, Settings, SignatureMode, CURRENT_SYMLINK, MULTISHELLS_DIR, MULTISHELL_ENV};
+use crate::download::download_resumable;
 use crate::error::{Result, RnmError};
-use crate::utils::{extract_tar_gz as decompress_tar_gz, extract_zip as decompress_zip};
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::lock::{clean_stale_staging, is_complete, RnmLock, COMPLETE_MARKER, MARKERS_MIGRATED, STAGING_PREFIX};
+use crate::mirror::MirrorList;
+use crate::project::find_project_version;
+use crate::shim::{find_executable, shim_file_name, DEFAULT_SHIMS, SHIMS_DIR, VERSION_ENV, WINDOWS_EXTENSIONS};
+use crate::remote::{fetch_bytes, fetch_text, index_url, parse_archive_name, parse_index, Platform, RemoteVersion};
+use crate::signature::{Keyring, SHASUMS_ASC_FILE, SHASUMS_SIG_FILE};
+use crate::version::VersionSpec;
+use crate::utils::{
+    extract_tar_gz as decompress_tar_gz, extract_tar_xz as decompress_tar_xz, extract_zip as decompress_zip,
+    file_url_path, StreamingExtractor,
+};
 use reqwest::Client;
-use scraper::{Html, Selector};
 use semver::Version;
+use serde::Serialize;
+use std::collections::BTreeSet;
 use std::env;
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::PathBuf;
+use std::future::Future;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tempfile::TempDir;
 
 
 #[cfg(unix)]
@@ -17,15 +34,48 @@ use std::os::unix::fs as unix_fs;
 #[cfg(windows)]
 use std::os::windows::fs as windows_fs;
 
+// multishell 链接超过这个时间没有更新、且 shell 已经退出时会被清理
+const MULTISHELL_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+// 从镜像或缓存获取到的安装包
+struct FetchedArchive {
+    path: PathBuf,
+    extension: &'static str,
+    // 下载时已经边下载边解压好的目录，校验和通过后才会使用
+    extracted: Option<TempDir>,
+}
+
+/// ls、ls-remote 和 current 返回的版本信息，字段名即 --json 输出的格式，修改时需要保持兼容
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionInfo {
+    pub version: String,
+    // LTS 代号，例如 "Iron"，非 LTS 版本或没有缓存的远程索引时为 None
+    pub lts: Option<String>,
+    // 发布日期，例如 "2024-02-14"
+    pub date: Option<String>,
+    pub installed: bool,
+    pub active: bool,
+    // 安装目录，未安装时为 None
+    pub path: Option<PathBuf>,
+}
+
 pub struct Manager {
     client: Client,
     versions_dir: PathBuf,
+    mirrors: MirrorList,
+    signature: SignatureMode,
+    keyring: Option<PathBuf>,
+    cache: Cache,
+    aliases: AliasStore,
+    download_retries: u32,
+    offline: bool,
+    archive_format: ArchiveFormat,
     #[allow(dead_code)]
     original_user: Option<String>,
 }
 
 impl Manager {
-    pub fn new() -> Result<Self> {
+    pub fn new(settings: &Settings) -> Result<Self> {
         let versions_dir = get_versions_dir()?;
         
         // 获取 SUDO_USER 环境变量，这是原始用户的用户名
@@ -55,90 +105,127 @@ impl Manager {
                     .output()?;
                 
                 if !output.status.success() {
-                    println!("警告: 修改目录所有权失败: {:?}", String::from_utf8_lossy(&output.stderr));
+                    eprintln!("警告: 修改目录所有权失败: {:?}", String::from_utf8_lossy(&output.stderr));
                 }
             } else {
                 fs::create_dir_all(&versions_dir)?;
             }
         }
 
-        Ok(Self {
+        let aliases = AliasStore::new(versions_dir.parent().unwrap_or(&versions_dir));
+
+        let manager = Self {
             client: Client::new(),
             versions_dir,
+            mirrors: MirrorList::new(settings.mirrors())?,
+            signature: settings.signature,
+            keyring: settings.keyring.clone(),
+            cache: Cache::new()?,
+            aliases,
+            download_retries: settings.download_retries(),
+            offline: settings.offline,
+            archive_format: settings.archive_format,
             original_user,
-        })
+        };
+        manager.migrate_complete_markers()?;
+        Ok(manager)
     }
 
-    pub async fn list_remote(&self, version_filter: Option<&str>) -> Result<Vec<String>> {
-        println!("正在从 {} 获取版本信息...", NODE_MIRROR);
-        let response = self.client.get(NODE_MIRROR).send().await?.text().await?;
-
-        let document = Html::parse_document(&response);
-        let selector = Selector::parse("a").map_err(|e| RnmError::HtmlParseError(e.to_string()))?;
-
-        let mut versions: Vec<Version> = document
-            .select(&selector)
-            .filter_map(|el| {
-                let text = el.text().collect::<String>();
-                text.trim_end_matches('/')
-                    .strip_prefix("v")
-                    .and_then(|v| Version::parse(v).ok())
-            })
-            .collect();
-
-        versions.sort();
-        versions.reverse();
-
-        // 如果指定了版本过滤
-        if let Some(filter) = version_filter {
-            let parts: Vec<&str> = filter.split('.').collect();
-            let filtered_versions: Vec<&Version> = versions.iter()
-                .filter(|v| {
-                    match parts.len() {
-                        1 => v.major.to_string() == parts[0],
-                        2 => v.major.to_string() == parts[0] && v.minor.to_string() == parts[1],
-                        3 => v.major.to_string() == parts[0] && v.minor.to_string() == parts[1] && v.patch.to_string() == parts[2],
-                        _ => false,
-                    }
-                })
-                .collect();
+    // 引入完成标记之前安装的版本没有标记，第一次运行时为其中 node 可执行文件存在的目录补写标记
+    fn migrate_complete_markers(&self) -> Result<()> {
+        let migrated = self.rnm_dir().join(MARKERS_MIGRATED);
+        if migrated.exists() {
+            return Ok(());
+        }
+        let _lock = RnmLock::acquire(self.rnm_dir())?;
+        // 等待锁期间其他进程可能已经完成了迁移
+        if migrated.exists() {
+            return Ok(());
+        }
 
-            if filtered_versions.is_empty() {
-                return Ok(vec![format!("未找到匹配版本 '{}'", filter)]);
+        for entry in fs::read_dir(&self.versions_dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name.starts_with("node-v")
+                && path.is_dir()
+                && !is_complete(&path)
+                && find_executable(&node_bin_dir(&path), "node").is_some()
+            {
+                fs::write(path.join(COMPLETE_MARKER), format!("{}\n", name))?;
             }
-
-            return Ok(filtered_versions.iter()
-                .map(|v| format!("\x1b[1;32m{}\x1b[0m", v))
-                .collect());
         }
+        fs::write(&migrated, "")?;
+        Ok(())
+    }
 
-        // 按主版本号分组显示
-        let mut grouped_versions: std::collections::BTreeMap<u64, Vec<&Version>> = std::collections::BTreeMap::new();
-        for version in &versions {
-            grouped_versions.entry(version.major).or_default().push(version);
+    /// 依次在各个镜像上执行请求，直到成功或遇到与镜像无关的错误，返回结果和使用的镜像
+    async fn with_mirrors<T, F, Fut>(&self, mut request: F) -> Result<(T, String)>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+        for mirror in self.mirrors.ordered() {
+            match request(mirror.clone()).await {
+                Ok(value) => {
+                    self.mirrors.record_success(&mirror);
+                    return Ok((value, mirror));
+                }
+                Err(e) if e.is_mirror_failure() => {
+                    eprintln!("镜像 {} 请求失败: {}", mirror, e);
+                    self.mirrors.record_failure(&mirror);
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
         }
+        Err(RnmError::AllMirrorsFailed(
+            last_error.map(|e| e.to_string()).unwrap_or_default(),
+        ))
+    }
 
-        // 只保留每个主版本号下最新的几个版本
-        let mut result = Vec::new();
-        for (major, versions) in grouped_versions.iter().rev().take(3) {  // 只显示最新的3个主版本
-            result.push(format!("\n\x1b[1;32mNode.js v{}.x\x1b[0m:", major));
-            for version in versions.iter().take(5) {  // 每个主版本只显示最新的5个版本
-                result.push(format!("  {}", version));
-            }
-            if versions.len() > 5 {
-                result.push(format!("  \x1b[90m... and {} more\x1b[0m", versions.len() - 5));
-            }
+    /// 从镜像获取远程版本索引，离线模式下使用上次缓存的索引
+    pub async fn remote_versions(&self) -> Result<Vec<RemoteVersion>> {
+        if self.offline {
+            return parse_index(&self.cache.load_index()?);
         }
 
-        Ok(result)
+        let (body, _) = self
+            .with_mirrors(|mirror| async move { fetch_text(&self.client, &index_url(&mirror)).await })
+            .await?;
+        let versions = parse_index(&body)?;
+        self.cache.save_index(&body)?;
+        Ok(versions)
+    }
+
+    /// 远程索引中的版本，按版本号从新到旧排序，可以按主版本号、次版本号或完整版本号过滤
+    pub async fn list_remote(&self, version_filter: Option<&str>) -> Result<Vec<VersionInfo>> {
+        let versions = self.remote_versions().await?;
+        let installed = self.get_installed_versions()?;
+        let active = self.current().await?;
+
+        let parts: Vec<&str> = version_filter.map(|filter| filter.split('.').collect()).unwrap_or_default();
+        Ok(versions
+            .iter()
+            .filter(|r| {
+                let v = &r.version;
+                match parts.len() {
+                    0 => true,
+                    1 => v.major.to_string() == parts[0],
+                    2 => v.major.to_string() == parts[0] && v.minor.to_string() == parts[1],
+                    3 => v.major.to_string() == parts[0] && v.minor.to_string() == parts[1] && v.patch.to_string() == parts[2],
+                    _ => false,
+                }
+            })
+            .map(|remote| self.version_info(&remote.version.to_string(), Some(remote), &installed, active.as_deref()))
+            .collect())
     }
 
     /// 获取所有已安装的版本列表
-    fn get_installed_versions(&self) -> Result<Vec<String>> {
-        println!("正在检查版本目录: {:?}", self.versions_dir);
-        
+    pub fn get_installed_versions(&self) -> Result<Vec<String>> {
         if !self.versions_dir.exists() {
-            println!("版本目录不存在，正在创建: {:?}", self.versions_dir);
             fs::create_dir_all(&self.versions_dir)?;
             return Ok(vec![]);
         }
@@ -154,7 +241,7 @@ impl Manager {
         let entries = match fs::read_dir(&self.versions_dir) {
             Ok(entries) => entries,
             Err(e) => {
-                println!("读取目录失败: {:?}, error: {}", self.versions_dir, e);
+                eprintln!("读取目录失败: {:?}, error: {}", self.versions_dir, e);
                 return Err(RnmError::FileIoError(e));
             }
         };
@@ -167,122 +254,315 @@ impl Manager {
                             if file_type.is_dir() {
                                 if let Some(name) = entry.file_name().to_str() {
                                     if name.starts_with("node-v") {
+                                        // 没有完成标记的目录是中断的安装，不算作已安装，下次安装该版本时会被替换
+                                        if !is_complete(&entry.path()) {
+                                            continue;
+                                        }
                                         let version = name.trim_start_matches("node-v").to_string();
                                         versions.push(version);
                                     }
                                 }
                             }
                         }
-                        Err(e) => println!("获取文件类型失败: {:?}, error: {}", entry.path(), e),
+                        Err(e) => eprintln!("获取文件类型失败: {:?}, error: {}", entry.path(), e),
                     }
                 }
-                Err(e) => println!("读取目录项失败: error: {}", e),
+                Err(e) => eprintln!("读取目录项失败: error: {}", e),
             }
         }
         versions.sort();
-        println!("找到已安装版本: {:?}", versions);
         Ok(versions)
     }
 
-    pub async fn list_local(&self) -> Result<Vec<String>> {
-        println!("Checking versions directory: {:?}", self.versions_dir);
-        
-        if !self.versions_dir.exists() {
-            println!("Creating versions directory as it doesn't exist");
-            fs::create_dir_all(&self.versions_dir)?;
-            return Ok(vec![]);
-        }
+    /// 已安装的版本，按版本号从旧到新排序，LTS 代号和发布日期来自缓存的远程索引
+    pub async fn list_local(&self) -> Result<Vec<VersionInfo>> {
+        let installed = self.get_installed_versions()?;
+        let active = self.current().await?;
+        let index = self.cached_index();
 
-        // 确保目录存在且可访问
-        if !self.versions_dir.is_dir() {
-            return Err(RnmError::FileIoError(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("{:?} is not a directory", self.versions_dir),
-            )));
-        }
+        let mut versions: Vec<VersionInfo> = installed
+            .iter()
+            .map(|version| {
+                let remote = index.iter().find(|r| r.version.to_string() == *version);
+                self.version_info(version, remote, &installed, active.as_deref())
+            })
+            .collect();
+        versions.sort_by_key(|info| Version::parse(&info.version).ok());
+        Ok(versions)
+    }
 
-        let versions = self.get_installed_versions()?;
-        
-        if versions.is_empty() {
-            println!("No versions found in the directory");
-            return Ok(vec![]);
+    /// 当前使用的版本及其安装目录
+    pub async fn current_info(&self) -> Result<Option<VersionInfo>> {
+        let Some(version) = self.current().await? else {
+            return Ok(None);
+        };
+        let installed = self.get_installed_versions()?;
+        let index = self.cached_index();
+        let remote = index.iter().find(|r| r.version.to_string() == version);
+        Ok(Some(self.version_info(&version, remote, &installed, Some(&version))))
+    }
+
+    fn version_info(&self, version: &str, remote: Option<&RemoteVersion>, installed: &[String], active: Option<&str>) -> VersionInfo {
+        let is_installed = installed.iter().any(|v| v == version);
+        VersionInfo {
+            version: version.to_string(),
+            lts: remote.and_then(|r| r.lts.clone()),
+            date: remote.map(|r| r.date.clone()),
+            installed: is_installed,
+            active: active == Some(version),
+            path: is_installed.then(|| self.versions_dir.join(format!("node-v{}", version))),
         }
+    }
 
+    // 缓存的远程索引，只用于补充版本信息，没有缓存时返回空列表，不发起网络请求
+    fn cached_index(&self) -> Vec<RemoteVersion> {
+        self.cache.load_index().and_then(|body| parse_index(&body)).unwrap_or_default()
+    }
 
-        
-        println!("Found {} installed versions: {:?}", versions.len(), versions);
-        Ok(versions)
+    /// 将版本描述（例如 `lts`、`latest`、`18`、`^18.12`）解析为远程索引中最新的匹配版本
+    pub async fn resolve_remote(&self, spec: &str) -> Result<RemoteVersion> {
+        let parsed: VersionSpec = self.aliases.expand(spec)?.parse()?;
+        let remote = self.remote_versions().await?;
+        parsed
+            .resolve_remote(&remote)
+            .cloned()
+            .ok_or_else(|| RnmError::VersionNotFound(spec.to_string()))
+    }
+
+    /// 将版本描述解析为已安装的版本号，未找到匹配的版本时返回 None
+    pub async fn resolve_installed(&self, spec: &str, installed: &[String]) -> Result<Option<String>> {
+        self.resolve_installed_with(spec, installed, false).await
+    }
+
+    // shim 和切换目录钩子每次调用都会解析版本，LTS 描述优先使用缓存的索引，没有缓存时才访问网络
+    async fn resolve_installed_cached(&self, spec: &str, installed: &[String]) -> Result<Option<String>> {
+        self.resolve_installed_with(spec, installed, true).await
+    }
+
+    async fn resolve_installed_with(&self, spec: &str, installed: &[String], prefer_cache: bool) -> Result<Option<String>> {
+        let parsed: VersionSpec = self.aliases.expand(spec)?.parse()?;
+        let installed: Vec<Version> = installed
+            .iter()
+            .filter_map(|v| Version::parse(v).ok())
+            .collect();
+
+        // 只有 LTS 类描述才需要访问远程索引
+        let remote = if !parsed.needs_lts_info() {
+            Vec::new()
+        } else if prefer_cache {
+            match self.cached_index() {
+                cached if cached.is_empty() => self.remote_versions().await?,
+                cached => cached,
+            }
+        } else {
+            self.remote_versions().await?
+        };
+
+        Ok(parsed
+            .resolve_installed(&installed, &remote)
+            .map(|v| v.to_string()))
     }
 
     pub async fn install(&self, version: &str) -> Result<()> {
-        let version_str = version.trim_start_matches('v').to_string();
+        // 通过远程索引解析版本，并确认为当前平台提供了安装包
+        let remote = self.resolve_remote(version).await?;
+        let version_str = remote.version.to_string();
         let version_dir = format!("node-v{}", version_str);
         let version_path = self.versions_dir.join(&version_dir);
-        if version_path.exists() {
-            return Err(RnmError::AlreadyInstalled(version_str.to_string()));
-        }
+        let _lock = self.lock()?;
+        self.check_not_installed(&version_path, &version_str)?;
 
         // 确定平台和架构
-        let os = env::consts::OS;
-        let arch = match env::consts::ARCH {
-            "x86_64" => "x64",
-            "aarch64" => "arm64",
-            _ => return Err(RnmError::UnsupportedArch(env::consts::ARCH.to_string())),
-        };
+        let platform = Platform::current()?;
+        if !remote.has_file(&platform.index_key()) {
+            return Err(RnmError::PlatformUnavailable(version_str, platform.index_key()));
+        }
 
-        let extension = if os == "windows" { "zip" } else { "tar.gz" };
-        
-        let os_name = match os {
-            "linux" => "linux",
-            "windows" => "win",
-            "macos" => "darwin",
-            _ => return Err(RnmError::UnsupportedPlatform(os.to_string(), arch.to_string())),
+        let extracted_dir_name = platform.dist_name(&remote.version);
+        let extensions = self.archive_extensions(&platform);
+
+        let (archive, source) = if self.offline {
+            // 离线模式下只能使用缓存中的安装包
+            let cached = extensions.iter().find_map(|ext| {
+                let filename = format!("{}.{}", extracted_dir_name, ext);
+                self.cache.find(&filename).transpose().map(|found| found.map(|path| (path, *ext)))
+            });
+            // 列出所有可以接受的文件名，缓存其中任意一个都可以安装
+            let (path, extension) = cached.transpose()?.ok_or_else(|| {
+                let filenames: Vec<String> =
+                    extensions.iter().map(|ext| format!("{}.{}", extracted_dir_name, ext)).collect();
+                RnmError::OfflineUnavailable(filenames.join(" 或 "))
+            })?;
+            (FetchedArchive { path, extension, extracted: None }, "offline cache".to_string())
+        } else {
+            self.fetch_archive(&version_str, &extracted_dir_name, &extensions).await?
         };
 
-        let filename = format!("node-v{}-{}-{}.{}", version_str, os_name, arch, extension);
-        let download_url = format!("{}/v{}/{}", NODE_MIRROR, version_str, filename);
+        self.unpack(&archive.path, archive.extension, &extracted_dir_name, &version_path, archive.extracted)?;
 
-        println!("Downloading from {} ...", download_url);
+        self.refresh_shims()?;
+        println!("Installed {} in {} (source: {})", version_str, version_path.display(), source);
+        Ok(())
+    }
 
-        // 下载并显示进度条
-        let mut response = self
-            .client
-            .get(&download_url)
-            .send()
-            .await?
-            .error_for_status()?;
-        let total_size = response.content_length().unwrap_or(0);
-        let pb = ProgressBar::new(total_size);
-        pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-        .unwrap()
-        .progress_chars("#>-"));
+    /// 使用本地安装包安装，版本号从文件名中解析，例如 node-v20.11.1-linux-x64.tar.gz
+    pub fn install_from_file(&self, archive: &Path, version: Option<&str>) -> Result<()> {
+        let filename = archive
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| RnmError::InvalidNodeVersion(archive.display().to_string()))?;
+        let (file_version, extracted_dir_name, extension) = parse_archive_name(filename)
+            .ok_or_else(|| RnmError::InvalidNodeVersion(filename.to_string()))?;
 
-        let temp_dir = tempfile::tempdir()?;
-        let temp_file_path = temp_dir.path().join(&filename);
+        // 同时指定了版本时，必须与安装包一致
+        if let Some(version) = version {
+            let spec: VersionSpec = version.parse()?;
+            if spec.resolve_installed(std::slice::from_ref(&file_version), &[]).is_none() {
+                return Err(RnmError::InvalidNodeVersion(format!(
+                    "{} 与安装包 {} 的版本不一致",
+                    version, filename
+                )));
+            }
+        }
+
+        let version_str = file_version.to_string();
+        let version_path = self.versions_dir.join(format!("node-v{}", version_str));
+        let _lock = self.lock()?;
+        self.check_not_installed(&version_path, &version_str)?;
 
-        let mut temp_file = File::create(&temp_file_path)?;
+        self.unpack(archive, extension, &extracted_dir_name, &version_path, None)?;
 
-        while let Some(chunk) = response.chunk().await? {
-            temp_file.write_all(&chunk)?;
-            pb.inc(chunk.len() as u64);
+        // 解压成功后才放入缓存，之后离线安装同一版本时可以直接使用
+        let sha256 = sha256_file(archive)?;
+        if self.cache.lookup(filename, &sha256)?.is_none() {
+            let staging = self.cache.temp_dir()?;
+            let staged = staging.path().join(filename);
+            fs::copy(archive, &staged)?;
+            self.cache.store(&staged, filename, &sha256)?;
         }
 
-        pb.finish_with_message("Download complete");
+        self.refresh_shims()?;
+        println!("Installed {} in {} (source: {})", version_str, version_path.display(), archive.display());
+        Ok(())
+    }
+
+    // 从镜像获取校验过的安装包，返回缓存中的安装包和使用的镜像
+    //
+    // extensions 按优先级排列，选用 SHASUMS256.txt 中列出的第一种格式。
+    async fn fetch_archive(
+        &self,
+        version_str: &str,
+        dist_name: &str,
+        extensions: &[&'static str],
+    ) -> Result<(FetchedArchive, String)> {
+        self
+            .with_mirrors(|mirror| {
+                let release_url = format!("{}/v{}", mirror, version_str);
+                async move {
+                    // 先获取同一镜像上的校验和，找不到时拒绝安装
+                    let shasums_url = format!("{}/{}", release_url, SHASUMS_FILE);
+                    let shasums = fetch_text(&self.client, &shasums_url).await?;
+                    self.verify_shasums_signature(&release_url, &shasums).await?;
+                    let (filename, extension, expected) = extensions
+                        .iter()
+                        .find_map(|ext| {
+                            let filename = format!("{}.{}", dist_name, ext);
+                            find_checksum(&shasums, &filename).map(|hash| (filename, *ext, hash))
+                        })
+                        .ok_or_else(|| RnmError::ChecksumMissing(format!("{}.{}", dist_name, extensions.join("|"))))?;
+                    let filename = filename.as_str();
+                    let download_url = format!("{}/{}", release_url, filename);
 
-        // 解压文件
-        println!("Unzipping file ...");
-        let extracted_dir_name = format!("node-v{}-{}-{}", version_str, os_name, arch);
-        let temp_extract_path = temp_dir.path();
+                    // 缓存中已有校验和一致的安装包时跳过下载
+                    if let Some(cached) = self.cache.lookup(filename, &expected)? {
+                        println!("Using cached {}", cached.display());
+                        return Ok(FetchedArchive { path: cached, extension, extracted: None });
+                    }
 
-        if extension == "zip" {
-            decompress_zip(&temp_file_path, temp_extract_path)?;
-        } else {
-            decompress_tar_gz(&temp_file_path, temp_extract_path)?;
+                    println!("Downloading from {} ...", download_url);
+                    if let Some(local_path) = file_url_path(&download_url) {
+                        // file:// 镜像直接复制本地文件
+                        let download_dir = self.cache.temp_dir()?;
+                        let download_path = download_dir.path().join(filename);
+                        let actual = copy_and_hash(&local_path, &download_path)?;
+                        verify(filename, &expected, &actual)?;
+                        // 其他镜像上未完成的下载已经没有用了
+                        let _ = fs::remove_file(self.cache.partial_path(filename, &expected)?);
+                        let path = self.cache.store(&download_path, filename, &expected)?;
+                        return Ok(FetchedArchive { path, extension, extracted: None });
+                    }
+
+                    // 下载到持久化的部分文件，中断后可以续传，tar 包同时边下载边解压
+                    let partial = self.cache.partial_path(filename, &expected)?;
+                    let mut extractor = StreamingExtractor::spawn(extension, self.staging_dir()?);
+                    // 解压发生在校验之前，只是预先进行：结果留在暂存目录中，校验通过后才会安装
+                    let downloaded = download_resumable(
+                        &self.client,
+                        &download_url,
+                        &partial,
+                        self.download_retries,
+                        extractor.as_mut(),
+                    )
+                    .await;
+                    // 下载失败或校验和不一致时丢弃已解压的内容
+                    let verified = downloaded.and_then(|actual| verify(filename, &expected, &actual));
+                    if let Err(e) = verified {
+                        if let Some(extractor) = extractor {
+                            extractor.discard();
+                        }
+                        if matches!(e, RnmError::ChecksumMismatch { .. }) {
+                            fs::remove_file(&partial)?;
+                        }
+                        return Err(e);
+                    }
+                    let extracted = extractor.and_then(StreamingExtractor::finish);
+                    let path = self.cache.store(&partial, filename, &expected)?;
+                    Ok(FetchedArchive { path, extension, extracted })
+                }
+            })
+            .await
+    }
+
+    // 当前平台可用的安装包格式，按优先级排列
+    fn archive_extensions(&self, platform: &Platform) -> Vec<&'static str> {
+        if platform.os == "win" {
+            return vec!["zip"];
+        }
+        match self.archive_format {
+            ArchiveFormat::Auto => vec!["tar.xz", "tar.gz"],
+            ArchiveFormat::Xz => vec!["tar.xz"],
+            ArchiveFormat::Gz => vec!["tar.gz"],
         }
+    }
+
+    // 解压安装包并移动到版本目录，extracted 为下载时已经解压好的目录
+    fn unpack(
+        &self,
+        archive_path: &Path,
+        extension: &str,
+        extracted_dir_name: &str,
+        version_path: &Path,
+        extracted: Option<TempDir>,
+    ) -> Result<()> {
+        let temp_dir = match extracted {
+            Some(temp_dir) => temp_dir,
+            None => {
+                // 解压文件
+                println!("Unzipping file ...");
+                let temp_dir = self.staging_dir()?;
+                let temp_extract_path = temp_dir.path();
+
+                match extension {
+                    "zip" => decompress_zip(archive_path, temp_extract_path)?,
+                    "tar.xz" => decompress_tar_xz(archive_path, temp_extract_path)?,
+                    _ => decompress_tar_gz(archive_path, temp_extract_path)?,
+                }
+                temp_dir
+            }
+        };
 
         // 移动文件到目标目录
-        let extracted_path = temp_dir.path().join(&extracted_dir_name);
+        let extracted_path = temp_dir.path().join(extracted_dir_name);
         
         if !extracted_path.exists() {
             return Err(RnmError::FileIoError(std::io::Error::new(
@@ -291,25 +571,90 @@ impl Manager {
             )));
         }
 
-        fs::rename(&extracted_path, &version_path)?;
-        fs::remove_file(&temp_file_path)?;
+        // 写入完成标记后再移动到版本目录，暂存目录与版本目录在同一文件系统，重命名是原子的
+        fs::write(extracted_path.join(COMPLETE_MARKER), format!("{}\n", extracted_dir_name))?;
+        fs::rename(&extracted_path, version_path)?;
+        Ok(())
+    }
+
+    // 获取 ~/.rnm 上的文件锁，并清理之前中断的安装留下的暂存目录
+    fn lock(&self) -> Result<RnmLock> {
+        let lock = RnmLock::acquire(self.rnm_dir())?;
+        clean_stale_staging(&self.versions_dir)?;
+        Ok(lock)
+    }
+
+    // rnm 的主目录，以 sudo 运行时为原始用户的目录
+    fn rnm_dir(&self) -> &Path {
+        self.versions_dir.parent().unwrap_or(&self.versions_dir)
+    }
+
+    // 在版本目录下创建暂存目录，解压完成前的内容都放在这里
+    fn staging_dir(&self) -> Result<TempDir> {
+        Ok(tempfile::Builder::new().prefix(STAGING_PREFIX).tempdir_in(&self.versions_dir)?)
+    }
 
-        println!("Installed {} in {}", version_str, version_path.display());
+    // 已完成的安装返回错误，未完成的安装直接删除以便重新安装，调用方需要持有锁
+    fn check_not_installed(&self, version_path: &Path, version: &str) -> Result<()> {
+        if is_complete(version_path) {
+            return Err(RnmError::AlreadyInstalled(version.to_string()));
+        }
+        if version_path.exists() {
+            println!("删除未完成的安装: {}", version_path.display());
+            fs::remove_dir_all(version_path)?;
+        }
         Ok(())
     }
 
-    pub fn use_version(&self, version: &str) -> Result<()> {
-        let version_str = version.trim_start_matches('v');
-        let version_dir = format!("node-v{}", version_str);
-        let version_path = self.versions_dir.join(&version_dir);
+    // 按配置校验 SHASUMS256.txt 的签名，warn 模式下校验失败只打印警告
+    async fn verify_shasums_signature(&self, release_url: &str, shasums: &str) -> Result<()> {
+        if self.signature == SignatureMode::Off {
+            return Ok(());
+        }
+
+        match self.check_shasums_signature(release_url, shasums).await {
+            Ok(()) => {
+                println!("SHASUMS256.txt 签名校验通过");
+                Ok(())
+            }
+            Err(e) if self.signature == SignatureMode::Warn => {
+                println!("警告: {}", e);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // 优先使用分离签名 SHASUMS256.txt.sig，获取失败时使用明文签名 SHASUMS256.txt.asc
+    async fn check_shasums_signature(&self, release_url: &str, shasums: &str) -> Result<()> {
+        let keyring = Keyring::load(self.keyring.as_deref())?;
+
+        let sig_url = format!("{}/{}", release_url, SHASUMS_SIG_FILE);
+        if let Ok(signature) = fetch_bytes(&self.client, &sig_url).await {
+            return keyring.verify_detached(shasums.as_bytes(), &signature);
+        }
+
+        let asc_url = format!("{}/{}", release_url, SHASUMS_ASC_FILE);
+        match fetch_text(&self.client, &asc_url).await {
+            Ok(armored) => keyring.verify_cleartext(shasums, &armored),
+            Err(e) => Err(RnmError::SignatureVerificationFailed(format!("无法获取签名文件: {}", e))),
+        }
+    }
 
+    /// 切换到已安装的版本
+    ///
+    /// 在 `rnm-rs env` 初始化的 shell 中只切换当前会话，否则更新 `~/.rnm/current`。
+    /// `system` 为 true 时改为在 /usr/local/bin 中创建链接，对所有用户生效。
+    pub async fn use_version(&self, version: &str, system: bool) -> Result<()> {
         // 获取已安装的版本列表
         let installed_versions = self.get_installed_versions()?;
-
-        println!("Installed versions: {:?}", installed_versions);
+        let resolved = self.resolve_installed(version, &installed_versions).await?;
+        let version_str = resolved.as_deref().unwrap_or(version);
+        let version_dir = format!("node-v{}", version_str);
+        let version_path = self.versions_dir.join(&version_dir);
 
         // 检查版本是否已安装
-        if !version_path.exists() || !installed_versions.contains(&version_str.to_string()) {
+        if resolved.is_none() || !version_path.exists() {
             println!("版本 '{}' 未安装。", version_str);
             if !installed_versions.is_empty() {
                 println!("当前已安装的版本：");
@@ -323,11 +668,37 @@ impl Manager {
             return Err(RnmError::NotInstalled(version_str.to_string()));
         }
 
-        let bin_path = version_path.join("bin");
+        let bin_path = node_bin_dir(&version_path);
         if !bin_path.exists() {
             return Err(RnmError::InvalidNodeVersion(version_str.to_string()));
         }
 
+        if system {
+            return self.use_system(version_str, &bin_path);
+        }
+
+        // 由 `rnm-rs env` 初始化的 shell 只切换当前会话，不需要 sudo
+        if let Ok(multishell) = env::var(MULTISHELL_ENV) {
+            replace_symlink_dir(&version_path, Path::new(&multishell))?;
+            println!("已在当前 shell 中切换到版本 {}", version_str);
+            return Ok(());
+        }
+
+        let current_link = self.rnm_dir().join(CURRENT_SYMLINK);
+        replace_symlink_dir(&version_path, &current_link)?;
+        println!("成功切换到版本 {}", version_str);
+
+        // ~/.rnm/current 只需要加入 PATH 一次
+        let current_bin = node_bin_dir(&current_link);
+        let in_path = env::var_os("PATH").is_some_and(|path| env::split_paths(&path).any(|p| p == current_bin));
+        if !in_path {
+            println!("\n提示: 请将 {} 加入 PATH，或在 shell 配置中加入 eval \"$(rnm-rs env)\"", current_bin.display());
+        }
+        Ok(())
+    }
+
+    // 在 /usr/local/bin 中创建 node、npm、npx 的链接，需要 sudo 权限
+    fn use_system(&self, version_str: &str, bin_path: &Path) -> Result<()> {
         let symlink_base = PathBuf::from("/usr/local/bin");
         println!(
             "切换到版本 {}。此操作需要 sudo 权限。",
@@ -337,7 +708,7 @@ impl Manager {
         // 检查是否有足够权限
         if !has_write_permission(&symlink_base) {
             println!("权限不足。请使用 sudo 运行此命令：");
-            println!("sudo rnm-rs use {}", version_str);
+            println!("sudo rnm-rs use --system {}", version_str);
             return Err(RnmError::FileIoError(std::io::Error::new(
                 std::io::ErrorKind::PermissionDenied,
                 "需要 sudo 权限",
@@ -367,7 +738,251 @@ impl Manager {
         Ok(())
     }
 
+    /// 在已安装和远程版本中选出满足描述的最佳版本并切换
+    ///
+    /// 优先使用已安装的最新匹配版本，没有匹配的已安装版本时安装远程最新的匹配版本。
+    pub async fn use_best_match(&self, spec: &str, system: bool) -> Result<()> {
+        let version = self.resolve_or_install(spec).await?;
+        self.use_version(&version, system).await
+    }
+
+    // 返回满足描述的最新已安装版本，没有时安装远程最新的匹配版本
+    async fn resolve_or_install(&self, spec: &str) -> Result<String> {
+        let installed_versions = self.get_installed_versions()?;
+        match self.resolve_installed(spec, &installed_versions).await? {
+            Some(version) => Ok(version),
+            None => {
+                let remote = self.resolve_remote(spec).await?;
+                println!("没有已安装的版本满足 '{}'，正在安装 {}", spec, remote.version);
+                self.install(&remote.version.to_string()).await?;
+                Ok(remote.version.to_string())
+            }
+        }
+    }
+
+    /// 已安装版本的目录，与 use 使用相同的解析规则，未指定版本时使用当前版本
+    pub async fn installed_path(&self, spec: Option<&str>) -> Result<PathBuf> {
+        let version = match spec {
+            Some(spec) => {
+                let installed_versions = self.get_installed_versions()?;
+                self.resolve_installed(spec, &installed_versions)
+                    .await?
+                    .ok_or_else(|| RnmError::NotInstalled(spec.to_string()))?
+            }
+            None => match self.current().await? {
+                Some(version) => version,
+                None => self.fallback_version().await?.ok_or(RnmError::NoVersionSelected)?,
+            },
+        };
+        Ok(self.versions_dir.join(format!("node-v{}", version)))
+    }
+
+    /// exec 和 run 使用的版本目录，不修改当前版本
+    pub async fn exec_version(&self, spec: &str, install_if_missing: bool) -> Result<PathBuf> {
+        if !install_if_missing {
+            return self.installed_path(Some(spec)).await;
+        }
+        let version = self.resolve_or_install(spec).await?;
+        Ok(self.versions_dir.join(format!("node-v{}", version)))
+    }
+
+    /// 由 `env --use-on-cd` 生成的 shell 钩子在切换目录时调用
+    ///
+    /// 按项目声明切换版本，没有项目声明或版本没有变化时不输出任何内容。
+    pub async fn use_on_cd(&self, install_if_missing: bool) -> Result<()> {
+        let Some(project) = find_project_version(&env::current_dir()?)? else {
+            return Ok(());
+        };
+
+        let installed_versions = self.get_installed_versions()?;
+        let version = match self.resolve_installed_cached(&project.spec, &installed_versions).await? {
+            Some(version) => version,
+            None if install_if_missing => {
+                let remote = self.resolve_remote(&project.spec).await?;
+                println!("{} 要求的版本 '{}' 未安装，正在安装 {}", project.source.display(), project.spec, remote.version);
+                self.install(&remote.version.to_string()).await?;
+                remote.version.to_string()
+            }
+            None => {
+                println!(
+                    "{} 要求的版本 '{}' 未安装，运行 `rnm-rs install` 安装",
+                    project.source.display(),
+                    project.spec
+                );
+                return Ok(());
+            }
+        };
+
+        if self.current().await?.as_deref() == Some(version.as_str()) {
+            return Ok(());
+        }
+        self.use_version(&version, false).await
+    }
+
+    /// 为新的 shell 会话创建 multishell 链接，初始指向当前使用的版本
+    pub async fn create_multishell(&self) -> Result<PathBuf> {
+        let dir = self.rnm_dir().join(MULTISHELLS_DIR);
+        fs::create_dir_all(&dir)?;
+        clean_stale_multishells(&dir)?;
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+        let path = dir.join(format!("{}_{}", shell_pid(), millis));
+
+        // 从上级 shell 继承的版本优先，其次是默认版本
+        let version = match self.multishell_version() {
+            Some(version) => Some(version),
+            None => self.fallback_version().await?,
+        };
+        if let Some(version) = version {
+            let version_path = self.versions_dir.join(format!("node-v{}", version));
+            if version_path.exists() {
+                replace_symlink_dir(&version_path, &path)?;
+            }
+        }
+        Ok(path)
+    }
+
     pub async fn current(&self) -> Result<Option<String>> {
+        // 当前 shell 通过 multishell 链接切换过版本时以它为准
+        if let Some(version) = self.multishell_version() {
+            return Ok(Some(version));
+        }
+
+        if let Some(version) = self.current_link_version() {
+            return Ok(Some(version));
+        }
+
+        // 兼容通过 use --system 切换的版本
+        self.system_current()
+    }
+
+    fn multishell_version(&self) -> Option<String> {
+        let multishell = env::var(MULTISHELL_ENV).ok()?;
+        version_from_dir(&fs::read_link(multishell).ok()?)
+    }
+
+    // ~/.rnm/current 指向的版本
+    fn current_link_version(&self) -> Option<String> {
+        let target = fs::read_link(self.rnm_dir().join(CURRENT_SYMLINK)).ok()?;
+        version_from_dir(&target)
+    }
+
+    // 没有其他地方指定版本时使用的版本：default 别名，其次是 ~/.rnm/current
+    async fn fallback_version(&self) -> Result<Option<String>> {
+        if self.aliases.get(DEFAULT_ALIAS)?.is_some() {
+            let installed_versions = self.get_installed_versions()?;
+            if let Some(version) = self.resolve_installed_cached(DEFAULT_ALIAS, &installed_versions).await? {
+                return Ok(Some(version));
+            }
+        }
+        Ok(self.current_link_version())
+    }
+
+    pub fn set_alias(&self, name: &str, spec: &str) -> Result<()> {
+        self.aliases.set(name, spec)
+    }
+
+    pub fn remove_alias(&self, name: &str) -> Result<()> {
+        self.aliases.remove(name)
+    }
+
+    /// 所有别名、对应的版本描述和当前解析到的已安装版本
+    pub async fn list_aliases(&self) -> Result<Vec<(String, String, Option<String>)>> {
+        let installed_versions = self.get_installed_versions()?;
+        let mut aliases = Vec::new();
+        for (name, spec) in self.aliases.list()? {
+            // 解析失败（例如离线时的 LTS 描述）只影响显示
+            let resolved = self.resolve_installed(&spec, &installed_versions).await.ok().flatten();
+            aliases.push((name, spec, resolved));
+        }
+        Ok(aliases)
+    }
+
+    /// shim 本次调用使用的版本目录，依次查看 RNM_NODE_VERSION、项目文件和默认版本
+    pub async fn shim_version(&self) -> Result<PathBuf> {
+        let spec = match env::var(VERSION_ENV) {
+            Ok(spec) if !spec.trim().is_empty() => Some(spec),
+            _ => find_project_version(&env::current_dir()?)?.map(|project| project.spec),
+        };
+
+        let version = match spec {
+            Some(spec) => {
+                let installed_versions = self.get_installed_versions()?;
+                self.resolve_installed_cached(&spec, &installed_versions)
+                    .await?
+                    .ok_or(RnmError::NotInstalled(spec))?
+            }
+            None => self.fallback_version().await?.ok_or(RnmError::NoVersionSelected)?,
+        };
+        Ok(self.versions_dir.join(format!("node-v{}", version)))
+    }
+
+    /// 重新生成 ~/.rnm/shims，返回 shim 目录和 shim 数量
+    pub fn reshim(&self) -> Result<(PathBuf, usize)> {
+        let _lock = self.lock()?;
+        self.write_shims()
+    }
+
+    // 安装或卸载后更新 shim，没有启用过 shim 时跳过，调用方需要持有锁
+    fn refresh_shims(&self) -> Result<()> {
+        if self.rnm_dir().join(SHIMS_DIR).is_dir() {
+            self.write_shims()?;
+        }
+        Ok(())
+    }
+
+    // 为默认命令和所有已安装版本 bin 目录中的命令创建指向 rnm-rs 的 shim
+    fn write_shims(&self) -> Result<(PathBuf, usize)> {
+        let mut names: BTreeSet<String> = DEFAULT_SHIMS.iter().map(|name| name.to_string()).collect();
+        for version in self.get_installed_versions()? {
+            let bin_dir = node_bin_dir(&self.versions_dir.join(format!("node-v{}", version)));
+            let Ok(entries) = fs::read_dir(&bin_dir) else {
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if let Some(name) = shim_name(&path) {
+                    names.insert(name);
+                }
+            }
+        }
+
+        // 先在同级的临时目录中生成新的 shim，再逐个重命名覆盖，更新过程中已有的 shim 始终可用
+        let dir = self.rnm_dir().join(SHIMS_DIR);
+        fs::create_dir_all(&dir)?;
+        let staging = tempfile::Builder::new().prefix(".shims-").tempdir_in(self.rnm_dir())?;
+
+        let exe = env::current_exe()?;
+        let mut files = BTreeSet::new();
+        for name in &names {
+            let file = shim_file_name(name);
+            let staged = staging.path().join(&file);
+            #[cfg(unix)]
+            unix_fs::symlink(&exe, &staged)?;
+            // Windows 上符号链接需要额外权限，复制一份可执行文件
+            #[cfg(windows)]
+            fs::copy(&exe, &staged)?;
+
+            let target = dir.join(&file);
+            match fs::rename(&staged, &target) {
+                Ok(()) => {}
+                // Windows 上正在运行的 shim 无法覆盖，保留旧文件，下次 reshim 时再更新
+                Err(_) if cfg!(windows) && target.exists() => {}
+                Err(e) => return Err(e.into()),
+            }
+            files.insert(file);
+        }
+
+        // 删除已经卸载的全局命令的 shim
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if !files.contains(entry.file_name().to_string_lossy().as_ref()) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        Ok((dir, names.len()))
+    }
+
+    fn system_current(&self) -> Result<Option<String>> {
         let node_path = PathBuf::from("/usr/local/bin/node");
         
         // 如果软链接不存在，直接返回 None
@@ -408,13 +1023,21 @@ impl Manager {
     }
 
     pub async fn uninstall(&self, version: &str) -> Result<()> {
-        let version_str = version.trim_start_matches('v');
+        let _lock = self.lock()?;
+        let installed_versions = self.get_installed_versions()?;
+        let version_str = self
+            .resolve_installed(version, &installed_versions)
+            .await?
+            .ok_or_else(|| RnmError::NotInstalled(version.to_string()))?;
         let version_dir = format!("node-v{}", version_str);
         let version_path = self.versions_dir.join(&version_dir);
         if !version_path.exists() {
             return Err(RnmError::NotInstalled(version.to_string()));
         }
+        // 先删除完成标记，删除中断时剩下的目录会被视为未完成的安装
+        fs::remove_file(version_path.join(COMPLETE_MARKER))?;
         fs::remove_dir_all(&version_path)?;
+        self.refresh_shims()?;
         println!(
             "Successfully uninstalled version {}",
             version_path.display()
@@ -423,6 +1046,125 @@ impl Manager {
     }
 }
 
+/// 版本目录中可执行文件所在的目录，Windows 安装包中 node.exe 位于根目录
+pub fn node_bin_dir(version_path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        version_path.to_path_buf()
+    } else {
+        version_path.join("bin")
+    }
+}
+
+// bin 目录中的文件对应的 shim 名称，rnm 开头的名称会被识别为 rnm-rs 本身，不能作为 shim
+fn shim_name(path: &Path) -> Option<String> {
+    let name = if cfg!(windows) {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        if !WINDOWS_EXTENSIONS.contains(&ext.as_str()) {
+            return None;
+        }
+        path.file_stem()?.to_str()?
+    } else {
+        path.file_name()?.to_str()?
+    };
+    if name.starts_with("rnm") || !path.is_file() {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+// 从 node-v<version> 形式的目录名中取出版本号
+fn version_from_dir(path: &Path) -> Option<String> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("node-v")
+        .map(|v| v.to_string())
+}
+
+// multishell 链接名中的进程号：Unix 上是执行 eval "$(rnm-rs env)" 的 shell，其他平台是 rnm-rs 本身
+fn shell_pid() -> u32 {
+    #[cfg(unix)]
+    {
+        std::os::unix::process::parent_id()
+    }
+    #[cfg(not(unix))]
+    {
+        process::id()
+    }
+}
+
+// 删除超过一天没有更新、且对应 shell 已经退出的 multishell 链接
+fn clean_stale_multishells(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(pid) = name.split('_').next().and_then(|pid| pid.parse::<u32>().ok()) else {
+            continue;
+        };
+        // use 切换版本时会替换链接，链接的修改时间就是最后一次使用的时间
+        let stale = fs::symlink_metadata(entry.path())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > MULTISHELL_MAX_AGE);
+        if stale && !process_alive(pid) {
+            let _ = fs::remove_file(entry.path()).or_else(|_| fs::remove_dir(entry.path()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // kill -0 只检查进程是否存在，不发送信号
+    process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+// 其他平台上链接名中的进程号不是 shell 的，无法判断 shell 是否仍在运行，不删除
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+// 将 link 指向 target 目录，已存在的链接会被替换
+fn replace_symlink_dir(target: &Path, link: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        // 先创建临时链接再重命名覆盖，切换过程中链接始终有效
+        let temp = link.with_extension("tmp");
+        let _ = fs::remove_file(&temp);
+        unix_fs::symlink(target, &temp)?;
+        fs::rename(&temp, link)?;
+    }
+    #[cfg(windows)]
+    {
+        if fs::symlink_metadata(link).is_ok() {
+            fs::remove_dir(link)?;
+        }
+        // 目录符号链接需要管理员权限或开发者模式，和 fnm 一样改用不需要特殊权限的目录联接
+        let output = process::Command::new("cmd")
+            .arg("/C")
+            .arg("mklink")
+            .arg("/J")
+            .arg(link)
+            .arg(target)
+            .output()?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "无法创建目录联接 {}: {}",
+                link.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
 fn has_write_permission(path: &PathBuf) -> bool {
     match fs::metadata(path) {
         Ok(_) => {
@@ -431,6 +1173,7 @@ fn has_write_permission(path: &PathBuf) -> bool {
             let result = fs::OpenOptions::new()
                 .write(true)
                 .create(true)
+                .truncate(true)
                 .open(&temp_file);
             
             // 如果临时文件创建成功，删除它
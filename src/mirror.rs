@@ -0,0 +1,137 @@
+use crate::config::get_rnm_dir;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const MIRROR_STATE_FILE: &str = "mirror-state.json";
+
+// 镜像请求失败后，在这段时间内排到其他镜像之后
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// 持久化的镜像健康状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct MirrorHealth {
+    // 最近一次请求成功的镜像，只作为记录，不影响尝试顺序
+    last_success: Option<String>,
+    // 每个镜像连续失败的次数，成功后清零
+    failures: BTreeMap<String, u32>,
+    // 每个镜像最近一次失败的时间（Unix 秒），成功后清除
+    failed_at: BTreeMap<String, u64>,
+}
+
+/// 带健康状态的有序镜像列表
+pub struct MirrorList {
+    mirrors: Vec<String>,
+    health: Mutex<MirrorHealth>,
+    state_path: PathBuf,
+}
+
+impl MirrorList {
+    pub fn new(mirrors: Vec<String>) -> Result<Self> {
+        let state_path = get_rnm_dir()?.join(MIRROR_STATE_FILE);
+        // 状态文件损坏时忽略，不影响正常使用
+        let health = fs::read_to_string(&state_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            mirrors,
+            health: Mutex::new(health),
+            state_path,
+        })
+    }
+
+    /// 本次尝试镜像的顺序：按配置顺序，最近失败过、仍在冷却期内的镜像排到最后
+    pub fn ordered(&self) -> Vec<String> {
+        let health = self.health.lock().unwrap();
+        let now = unix_now();
+        let mut mirrors = self.mirrors.clone();
+        // 稳定排序，同一组内保持配置顺序
+        mirrors.sort_by_key(|m| {
+            health
+                .failed_at
+                .get(m)
+                .is_some_and(|failed| now.saturating_sub(*failed) < FAILURE_COOLDOWN.as_secs())
+        });
+        mirrors
+    }
+
+    pub fn record_success(&self, mirror: &str) {
+        let mut health = self.health.lock().unwrap();
+        health.last_success = Some(mirror.to_string());
+        health.failures.remove(mirror);
+        health.failed_at.remove(mirror);
+        self.save(&health);
+    }
+
+    pub fn record_failure(&self, mirror: &str) {
+        let mut health = self.health.lock().unwrap();
+        *health.failures.entry(mirror.to_string()).or_default() += 1;
+        health.failed_at.insert(mirror.to_string(), unix_now());
+        if health.last_success.as_deref() == Some(mirror) {
+            health.last_success = None;
+        }
+        self.save(&health);
+    }
+
+    // 状态只用于调整顺序，写入失败时不中断当前操作
+    fn save(&self, health: &MirrorHealth) {
+        if let Ok(content) = serde_json::to_string_pretty(health) {
+            let _ = fs::write(&self.state_path, content);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirror_list(dir: &tempfile::TempDir, health: MirrorHealth) -> MirrorList {
+        MirrorList {
+            mirrors: vec!["primary".to_string(), "fallback".to_string()],
+            health: Mutex::new(health),
+            state_path: dir.path().join(MIRROR_STATE_FILE),
+        }
+    }
+
+    #[test]
+    fn last_success_does_not_override_configured_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let health = MirrorHealth { last_success: Some("fallback".to_string()), ..Default::default() };
+        assert_eq!(mirror_list(&dir, health).ordered(), ["primary", "fallback"]);
+    }
+
+    #[test]
+    fn recent_failure_moves_mirror_to_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let mirrors = mirror_list(&dir, MirrorHealth::default());
+        mirrors.record_failure("primary");
+        mirrors.record_success("fallback");
+        assert_eq!(mirrors.ordered(), ["fallback", "primary"]);
+
+        mirrors.record_success("primary");
+        assert_eq!(mirrors.ordered(), ["primary", "fallback"]);
+    }
+
+    #[test]
+    fn failure_expires_after_cooldown() {
+        let dir = tempfile::tempdir().unwrap();
+        let failed = unix_now() - FAILURE_COOLDOWN.as_secs() - 1;
+        let health = MirrorHealth {
+            failures: BTreeMap::from([("primary".to_string(), 3)]),
+            failed_at: BTreeMap::from([("primary".to_string(), failed)]),
+            ..Default::default()
+        };
+        assert_eq!(mirror_list(&dir, health).ordered(), ["primary", "fallback"]);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::cli::OutputFormat;
+use crate::error::{Result, RnmError};
+use crate::manager::VersionInfo;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal};
+
+// ls-remote 不带过滤条件时显示的主版本数，以及每个主版本显示的版本数
+const REMOTE_MAJORS: usize = 3;
+const REMOTE_PER_MAJOR: usize = 5;
+
+const TABLE_HEADER: [&str; 6] = ["VERSION", "LTS", "DATE", "INSTALLED", "ACTIVE", "PATH"];
+
+/// 输出 ls-remote 的结果
+pub fn print_remote(versions: &[VersionInfo], filter: Option<&str>, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => print_json(&versions),
+        OutputFormat::Table => {
+            print_table(versions);
+            Ok(())
+        }
+        OutputFormat::Plain => {
+            print_remote_plain(versions, filter);
+            Ok(())
+        }
+    }
+}
+
+/// 输出 ls 的结果
+pub fn print_local(versions: &[VersionInfo], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => return print_json(&versions),
+        OutputFormat::Table => print_table(versions),
+        OutputFormat::Plain => {
+            println!("Installed Node.js versions:");
+            if versions.is_empty() {
+                println!("No versions installed");
+            }
+            for version in versions {
+                let marker = if version.active { "*" } else { " " };
+                println!("{} {}", marker, version.version);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 输出 current 的结果，JSON 格式下没有当前版本时输出 null
+pub fn print_current(version: Option<&VersionInfo>, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => return print_json(&version),
+        OutputFormat::Table => print_table(version.map(std::slice::from_ref).unwrap_or_default()),
+        OutputFormat::Plain => match version {
+            Some(version) => println!("Current active version: {}", version.version),
+            None => println!("No version is currently active through rnm"),
+        },
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| RnmError::JsonOutputError(e.to_string()))?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn print_table(versions: &[VersionInfo]) {
+    let rows: Vec<[String; 6]> = versions
+        .iter()
+        .map(|v| {
+            [
+                v.version.clone(),
+                v.lts.clone().unwrap_or_else(|| "-".to_string()),
+                v.date.clone().unwrap_or_else(|| "-".to_string()),
+                yes_no(v.installed).to_string(),
+                yes_no(v.active).to_string(),
+                v.path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    let mut widths = TABLE_HEADER.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    print_row(&TABLE_HEADER.map(str::to_string), &widths);
+    for row in &rows {
+        print_row(row, &widths);
+    }
+}
+
+// 最后一列不补空格，避免行尾多余的空白
+fn print_row(row: &[String; 6], widths: &[usize; 6]) {
+    let line: Vec<String> = row
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect();
+    println!("{}", line.join("  ").trim_end());
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+fn print_remote_plain(versions: &[VersionInfo], filter: Option<&str>) {
+    let paint = Painter::new();
+    println!("\n可用的 Node.js 版本:");
+
+    if let Some(filter) = filter {
+        if versions.is_empty() {
+            println!("未找到匹配版本 '{}'", filter);
+        }
+        for version in versions {
+            println!("{}{}", paint.green(&version.version), describe(version, &paint));
+        }
+        return;
+    }
+
+    // 按主版本号分组显示，只显示最新的几个主版本，每个主版本只显示最新的几个版本
+    let mut grouped: BTreeMap<&str, Vec<&VersionInfo>> = BTreeMap::new();
+    let mut majors: Vec<&str> = Vec::new();
+    for version in versions {
+        let major = version.version.split('.').next().unwrap_or_default();
+        if !majors.contains(&major) {
+            majors.push(major);
+        }
+        grouped.entry(major).or_default().push(version);
+    }
+    for major in majors.into_iter().take(REMOTE_MAJORS) {
+        let versions = &grouped[major];
+        println!("\n{}:", paint.green(&format!("Node.js v{}.x", major)));
+        for version in versions.iter().take(REMOTE_PER_MAJOR) {
+            println!("  {}{}", version.version, describe(version, &paint));
+        }
+        if versions.len() > REMOTE_PER_MAJOR {
+            println!("  {}", paint.gray(&format!("... and {} more", versions.len() - REMOTE_PER_MAJOR)));
+        }
+    }
+
+    println!("\n提示: 使用 `rnm-rs ls-remote <version>` 查看特定版本");
+    println!("例如: rnm-rs ls-remote 18 或 rnm-rs ls-remote 18.15\n");
+}
+
+// 版本列表中附加的 LTS 代号、发布日期和安装状态
+fn describe(version: &VersionInfo, paint: &Painter) -> String {
+    let mut text = String::new();
+    if let Some(codename) = &version.lts {
+        text.push_str(&format!("  {}", paint.blue(&format!("LTS: {}", codename))));
+    }
+    if let Some(date) = &version.date {
+        text.push_str(&format!("  {}", date));
+    }
+    if version.active {
+        text.push_str("  (active)");
+    } else if version.installed {
+        text.push_str("  (installed)");
+    }
+    text
+}
+
+// 只在输出到终端时使用颜色，重定向到文件或管道时输出纯文本
+struct Painter {
+    enabled: bool,
+}
+
+impl Painter {
+    fn new() -> Self {
+        Self { enabled: io::stdout().is_terminal() }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn green(&self, text: &str) -> String {
+        self.paint("1;32", text)
+    }
+
+    fn blue(&self, text: &str) -> String {
+        self.paint("1;34", text)
+    }
+
+    fn gray(&self, text: &str) -> String {
+        self.paint("90", text)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::alias::{AliasStore, DEFAULT_ALIAS};
+use crate::config::get_rnm_dir;
+use crate::error::{Result, RnmError};
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 按优先级排列的项目版本文件
+pub const VERSION_FILES: [&str; 2] = [".nvmrc", ".node-version"];
+
+pub const PACKAGE_JSON: &str = "package.json";
+
+/// 项目版本的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectSource {
+    // .nvmrc / .node-version
+    VersionFile,
+    // package.json 中的 volta.node，视为精确版本
+    Volta,
+    // package.json 中的 engines.node，视为版本范围
+    Engines,
+}
+
+impl fmt::Display for ProjectSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VersionFile => write!(f, "version file"),
+            Self::Volta => write!(f, "volta.node"),
+            Self::Engines => write!(f, "engines.node"),
+        }
+    }
+}
+
+/// 从项目文件中读取到的版本描述
+#[derive(Debug, Clone)]
+pub struct ProjectVersion {
+    pub spec: String,
+    pub source: PathBuf,
+    pub kind: ProjectSource,
+}
+
+// 从 start 开始逐级向上查找最近的项目版本声明
+//
+// 同一目录下的优先级为 .nvmrc > .node-version > volta.node > engines.node。
+pub fn find_project_version(start: &Path) -> Result<Option<ProjectVersion>> {
+    for dir in start.ancestors() {
+        for name in VERSION_FILES {
+            let path = dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)?;
+            if let Some(spec) = parse_version_file(&content) {
+                return Ok(Some(ProjectVersion { spec, source: path, kind: ProjectSource::VersionFile }));
+            }
+        }
+
+        let path = dir.join(PACKAGE_JSON);
+        if path.is_file() {
+            let content = fs::read_to_string(&path)?;
+            if let Some((spec, kind)) = parse_package_json(&content) {
+                return Ok(Some(ProjectVersion { spec, source: path, kind }));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// 解析版本文件内容，忽略空行和 `#` 开头的注释，返回第一个版本描述
+pub fn parse_version_file(content: &str) -> Option<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .find(|line| !line.is_empty())
+        .and_then(|line| line.split_whitespace().next())
+        .map(|spec| spec.to_string())
+}
+
+/// 从 package.json 中读取 volta.node 或 engines.node，格式错误的文件视为未声明
+pub fn parse_package_json(content: &str) -> Option<(String, ProjectSource)> {
+    let json: Value = serde_json::from_str(content).ok()?;
+
+    let field = |section: &str| {
+        json.get(section)
+            .and_then(|s| s.get("node"))
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    if let Some(pin) = field("volta") {
+        // volta 固定的是完整版本号，按精确版本匹配
+        let spec = if pin.starts_with('=') { pin } else { format!("={}", pin.trim_start_matches('v')) };
+        return Some((spec, ProjectSource::Volta));
+    }
+    field("engines").map(|range| (range, ProjectSource::Engines))
+}
+
+// 未显式指定版本时，从当前目录向上查找项目版本声明，没有项目声明时使用 default 别名
+pub fn version_or_project(version: Option<String>) -> Result<String> {
+    if let Some(version) = version {
+        return Ok(version);
+    }
+    let cwd = std::env::current_dir()?;
+    if let Some(project) = find_project_version(&cwd)? {
+        announce(&project);
+        return Ok(project.spec);
+    }
+    // 别名会在解析版本时展开，这里直接返回别名本身
+    if AliasStore::new(&get_rnm_dir()?).get(DEFAULT_ALIAS)?.is_some() {
+        return Ok(DEFAULT_ALIAS.to_string());
+    }
+    Err(RnmError::NoProjectVersion(cwd.display().to_string()))
+}
+
+// 查找当前目录对应的项目版本声明，未找到时返回错误
+pub fn project_version() -> Result<ProjectVersion> {
+    let cwd = std::env::current_dir()?;
+    match find_project_version(&cwd)? {
+        Some(project) => {
+            announce(&project);
+            Ok(project)
+        }
+        None => Err(RnmError::NoProjectVersion(cwd.display().to_string())),
+    }
+}
+
+// 输出到 stderr，不影响 exec 等命令的标准输出
+fn announce(project: &ProjectVersion) {
+    eprintln!(
+        "使用 {} 中指定的版本 ({}): {}",
+        project.source.display(),
+        project.kind,
+        project.spec
+    );
+}
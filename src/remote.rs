@@ -0,0 +1,138 @@
+use crate::error::{Result, RnmError};
+use crate::utils::file_url_path;
+use reqwest::Client;
+use semver::Version;
+use serde::{Deserialize, Deserializer};
+use std::env;
+
+/// `dist/index.json` 中的一条发布记录
+#[derive(Debug, Clone, Deserialize)]
+#[allow(dead_code)]
+pub struct RemoteVersion {
+    #[serde(deserialize_with = "deserialize_version")]
+    pub version: Version,
+    pub date: String,
+    #[serde(default)]
+    pub npm: Option<String>,
+    #[serde(default)]
+    pub v8: Option<String>,
+    // index.json 中非 LTS 版本为 false，LTS 版本为代号字符串，例如 "Iron"
+    #[serde(deserialize_with = "deserialize_lts")]
+    pub lts: Option<String>,
+    #[serde(default)]
+    pub security: bool,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+impl RemoteVersion {
+    /// 该版本是否为指定平台发布了二进制包
+    pub fn has_file(&self, key: &str) -> bool {
+        self.files.iter().any(|f| f == key)
+    }
+}
+
+fn deserialize_version<'de, D>(deserializer: D) -> std::result::Result<Version, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Version::parse(raw.trim_start_matches('v')).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_lts<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(codename) => Some(codename),
+        _ => None,
+    })
+}
+
+// 镜像上 index.json 的地址
+pub fn index_url(mirror: &str) -> String {
+    format!("{}/index.json", mirror.trim_end_matches('/'))
+}
+
+// 获取镜像上的二进制文件，支持 file:// 形式的本地镜像
+pub async fn fetch_bytes(client: &Client, url: &str) -> Result<Vec<u8>> {
+    match file_url_path(url) {
+        Some(path) => Ok(std::fs::read(path)?),
+        None => Ok(client.get(url).send().await?.error_for_status()?.bytes().await?.to_vec()),
+    }
+}
+
+// 获取镜像上的文本文件，支持 file:// 形式的本地镜像
+pub async fn fetch_text(client: &Client, url: &str) -> Result<String> {
+    match file_url_path(url) {
+        Some(path) => Ok(std::fs::read_to_string(path)?),
+        None => Ok(client.get(url).send().await?.error_for_status()?.text().await?),
+    }
+}
+
+// 解析 index.json，按版本号从新到旧排序
+pub fn parse_index(body: &str) -> Result<Vec<RemoteVersion>> {
+    let mut versions: Vec<RemoteVersion> =
+        serde_json::from_str(body).map_err(|e| RnmError::IndexParseError(e.to_string()))?;
+    versions.sort_by(|a, b| b.version.cmp(&a.version));
+    Ok(versions)
+}
+
+/// 当前运行平台在 Node.js 发布包中的命名
+#[derive(Debug, Clone, Copy)]
+pub struct Platform {
+    pub os: &'static str,
+    pub arch: &'static str,
+}
+
+impl Platform {
+    pub fn current() -> Result<Self> {
+        let arch = match env::consts::ARCH {
+            "x86_64" => "x64",
+            "aarch64" => "arm64",
+            _ => return Err(RnmError::UnsupportedArch(env::consts::ARCH.to_string())),
+        };
+
+        let os = match env::consts::OS {
+            "linux" => "linux",
+            "windows" => "win",
+            "macos" => "darwin",
+            other => return Err(RnmError::UnsupportedPlatform(other.to_string(), arch.to_string())),
+        };
+
+        Ok(Self { os, arch })
+    }
+
+    /// index.json `files` 字段中对应的标识，例如 linux-x64、osx-arm64-tar、win-x64-zip
+    pub fn index_key(&self) -> String {
+        match self.os {
+            "darwin" => format!("osx-{}-tar", self.arch),
+            "win" => format!("win-{}-zip", self.arch),
+            _ => format!("{}-{}", self.os, self.arch),
+        }
+    }
+
+    /// 解压后的目录名，例如 node-v20.11.1-linux-x64
+    pub fn dist_name(&self, version: &Version) -> String {
+        format!("node-v{}-{}-{}", version, self.os, self.arch)
+    }
+}
+
+// 本地安装包支持的扩展名
+pub const ARCHIVE_EXTENSIONS: [&str; 3] = ["tar.xz", "tar.gz", "zip"];
+
+/// 从安装包文件名中解析版本、解压后的目录名和扩展名
+///
+/// 例如 `node-v20.11.1-linux-x64.tar.gz` 解析为 (20.11.1, node-v20.11.1-linux-x64, tar.gz)。
+pub fn parse_archive_name(filename: &str) -> Option<(Version, String, &'static str)> {
+    let (dist_name, extension) = ARCHIVE_EXTENSIONS.iter().find_map(|ext| {
+        filename
+            .strip_suffix(ext)
+            .and_then(|stem| stem.strip_suffix('.'))
+            .map(|stem| (stem, *ext))
+    })?;
+    let version = dist_name.strip_prefix("node-v")?.split('-').next()?;
+    let version = Version::parse(version).ok()?;
+    Some((version, dist_name.to_string(), extension))
+}
@@ -0,0 +1,120 @@
+use clap::ValueEnum;
+use std::env;
+use std::fmt;
+use std::path::Path;
+
+/// `rnm-rs env` 支持的 shell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(name = "powershell", alias = "pwsh")]
+    Powershell,
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bash => write!(f, "bash"),
+            Self::Zsh => write!(f, "zsh"),
+            Self::Fish => write!(f, "fish"),
+            Self::Powershell => write!(f, "powershell"),
+        }
+    }
+}
+
+impl Shell {
+    /// 根据 $SHELL 推断当前 shell，无法判断时 Windows 上使用 PowerShell，其余使用 bash
+    pub fn detect() -> Self {
+        let name = env::var("SHELL").unwrap_or_default();
+        let name = Path::new(&name)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match name.as_str() {
+            "zsh" => Self::Zsh,
+            "fish" => Self::Fish,
+            "pwsh" | "powershell" => Self::Powershell,
+            "bash" => Self::Bash,
+            _ if cfg!(windows) => Self::Powershell,
+            _ => Self::Bash,
+        }
+    }
+
+    /// 设置环境变量的语句
+    pub fn set_env(&self, name: &str, value: &str) -> String {
+        match self {
+            Self::Bash | Self::Zsh => format!("export {}={}", name, quote_posix(value)),
+            Self::Fish => format!("set -gx {} {}", name, quote_posix(value)),
+            Self::Powershell => format!("$env:{} = {}", name, quote_powershell(value)),
+        }
+    }
+
+    /// 将目录加到 PATH 最前面的语句
+    pub fn prepend_path(&self, dir: &str) -> String {
+        match self {
+            Self::Bash | Self::Zsh => format!("export PATH={}:\"$PATH\"", quote_posix(dir)),
+            Self::Fish => format!("set -gx PATH {} $PATH", quote_posix(dir)),
+            Self::Powershell => format!(
+                "$env:PATH = {} + [System.IO.Path]::PathSeparator + $env:PATH",
+                quote_powershell(dir)
+            ),
+        }
+    }
+
+    /// 切换目录时执行 command 的钩子
+    pub fn use_on_cd_hook(&self, command: &str) -> String {
+        match self {
+            // PROMPT_COMMAND 在每次显示提示符前执行，只在目录变化时调用
+            Self::Bash => format!(
+                r#"__rnm_use_on_cd() {{
+  if [ "$__RNM_LAST_PWD" != "$PWD" ]; then
+    __RNM_LAST_PWD="$PWD"
+    {command}
+  fi
+}}
+if [[ ";${{PROMPT_COMMAND:-}};" != *";__rnm_use_on_cd;"* ]]; then
+  PROMPT_COMMAND="__rnm_use_on_cd${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi"#
+            ),
+            Self::Zsh => format!(
+                r#"autoload -U add-zsh-hook
+__rnm_use_on_cd() {{
+  {command}
+}}
+add-zsh-hook chpwd __rnm_use_on_cd
+__rnm_use_on_cd"#
+            ),
+            Self::Fish => format!(
+                r#"function __rnm_use_on_cd --on-variable PWD --description 'Switch Node.js version on cd'
+  status --is-command-substitution; and return
+  {command}
+end
+__rnm_use_on_cd"#
+            ),
+            // PowerShell 没有目录切换事件，改为包装 cd 命令
+            Self::Powershell => format!(
+                r#"function global:__RnmUseOnCd {{
+  {command}
+}}
+function global:Set-LocationWithRnm {{
+  param($path)
+  if ($path -eq $null) {{ Set-Location }} else {{ Set-Location $path }}
+  __RnmUseOnCd
+}}
+Set-Alias -Scope global cd Set-LocationWithRnm -Force
+__RnmUseOnCd"#
+            ),
+        }
+    }
+}
+
+// 单引号内的内容不做任何展开，单引号本身需要先结束引号再转义
+fn quote_posix(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn quote_powershell(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
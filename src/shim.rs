@@ -0,0 +1,74 @@
+use crate::config::{get_rnm_dir, Settings};
+use crate::error::{Result, RnmError};
+use crate::manager::{node_bin_dir, Manager};
+use crate::utils::exec_in_path;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SHIMS_DIR: &str = "shims";
+
+// 为这些命令始终创建 shim，其余命令来自各版本 bin 目录中全局安装的 npm 包
+pub const DEFAULT_SHIMS: [&str; 4] = ["node", "npm", "npx", "corepack"];
+
+// Windows 上可以直接执行的文件扩展名
+pub const WINDOWS_EXTENSIONS: [&str; 3] = ["exe", "cmd", "bat"];
+
+// 为单次调用指定版本，优先级高于项目文件和默认版本
+pub const VERSION_ENV: &str = "RNM_NODE_VERSION";
+
+/// 以 shim 的名字被调用时返回对应的命令名，例如通过 ~/.rnm/shims/node 调用时返回 node
+///
+/// 只有从 shims 目录启动，或者名字是已生成的 shim 时才进入 shim 模式，改名或打包后的 rnm-rs 不受影响。
+pub fn invoked_as() -> Option<String> {
+    let argv0 = PathBuf::from(env::args_os().next()?);
+    let name = argv0.file_stem()?.to_str()?.to_string();
+    if name.starts_with("rnm") {
+        return None;
+    }
+
+    let shims_dir = get_rnm_dir().ok()?.join(SHIMS_DIR);
+    // 通过 PATH 查找时 argv[0] 可能只有命令名，没有所在目录
+    let in_shims_dir = match (argv0.parent().and_then(|p| p.canonicalize().ok()), shims_dir.canonicalize().ok()) {
+        (Some(parent), Some(shims_dir)) => parent == shims_dir,
+        _ => false,
+    };
+    let generated = fs::symlink_metadata(shims_dir.join(shim_file_name(&name))).is_ok();
+    (in_shims_dir || generated).then_some(name)
+}
+
+/// shims 目录中命令对应的文件名，Windows 上是复制的可执行文件
+pub fn shim_file_name(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// shim 模式：解析本次调用使用的版本，并执行该版本中的同名命令
+pub async fn run(name: &str) -> Result<i32> {
+    // shim 没有命令行参数，但和普通命令一样读取 RNM_NODE_MIRROR 等环境变量
+    let settings = Settings::load()?.with_overrides(None, false);
+    let manager = Manager::new(&settings)?;
+    let version_path = manager.shim_version().await?;
+
+    let bin_dir = node_bin_dir(&version_path);
+    let program = find_executable(&bin_dir, name).ok_or_else(|| {
+        RnmError::CommandNotFound(name.to_string(), version_path.display().to_string())
+    })?;
+    let args: Vec<OsString> = env::args_os().skip(1).collect();
+    exec_in_path(program.as_os_str(), &args, &bin_dir)
+}
+
+/// 在版本的 bin 目录中查找命令，Windows 上依次尝试常见的可执行文件扩展名
+pub fn find_executable(bin_dir: &Path, name: &str) -> Option<PathBuf> {
+    if cfg!(windows) {
+        return WINDOWS_EXTENSIONS
+            .iter()
+            .map(|ext| bin_dir.join(format!("{}.{}", name, ext)))
+            .find(|path| path.is_file());
+    }
+    Some(bin_dir.join(name)).filter(|path| path.is_file())
+}
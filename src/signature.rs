@@ -0,0 +1,179 @@
+use crate::error::{Result, RnmError};
+use pgp::cleartext::CleartextSignedMessage;
+use pgp::{Deserializable, SignedPublicKey, StandaloneSignature};
+use std::fs;
+use std::path::Path;
+
+pub const SHASUMS_SIG_FILE: &str = "SHASUMS256.txt.sig";
+pub const SHASUMS_ASC_FILE: &str = "SHASUMS256.txt.asc";
+
+// 编译时内置的 Node.js 发布团队公钥，通过 scripts/update-release-keys.sh 更新
+const BUNDLED_KEYRING: &str = include_str!("../keys/nodejs-release-keys.asc");
+
+const KEY_BLOCK_BEGIN: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----";
+const KEY_BLOCK_END: &str = "-----END PGP PUBLIC KEY BLOCK-----";
+
+/// 用于校验 SHASUMS256.txt 签名的公钥集合
+pub struct Keyring {
+    keys: Vec<SignedPublicKey>,
+}
+
+impl Keyring {
+    // 加载内置公钥，以及用户额外指定的公钥文件
+    pub fn load(extra: Option<&Path>) -> Result<Self> {
+        let mut keys = parse_keys(BUNDLED_KEYRING)?;
+        if let Some(path) = extra {
+            keys.extend(parse_keys(&fs::read_to_string(path)?)?);
+        }
+        Ok(Self { keys })
+    }
+
+    /// 校验分离签名（SHASUMS256.txt.sig），签名可以是二进制或 armored 格式
+    pub fn verify_detached(&self, content: &[u8], signature: &[u8]) -> Result<()> {
+        let signature = if signature.starts_with(b"-----BEGIN") {
+            StandaloneSignature::from_armor_single(signature).map(|(sig, _)| sig)
+        } else {
+            StandaloneSignature::from_bytes(signature)
+        }
+        .map_err(|e| RnmError::SignatureVerificationFailed(format!("无法解析签名: {}", e)))?;
+
+        self.verify_with_any_key(&[signature], content)
+    }
+
+    /// 校验明文签名（SHASUMS256.txt.asc），并确认签名的内容与 SHASUMS256.txt 一致
+    pub fn verify_cleartext(&self, content: &str, armored: &str) -> Result<()> {
+        let (message, _) = CleartextSignedMessage::from_string(armored)
+            .map_err(|e| RnmError::SignatureVerificationFailed(format!("无法解析签名: {}", e)))?;
+
+        let signed_text = message.signed_text();
+        let signed = signed_text.lines().map(str::trim_end);
+        let expected = content.lines().map(str::trim_end);
+        if !signed.eq(expected) {
+            return Err(RnmError::SignatureVerificationFailed(
+                "签名内容与 SHASUMS256.txt 不一致".to_string(),
+            ));
+        }
+
+        self.verify_with_any_key(message.signatures(), signed_text.as_bytes())
+    }
+
+    // 任一签名被主密钥或任一子密钥校验通过即可
+    fn verify_with_any_key(&self, signatures: &[StandaloneSignature], content: &[u8]) -> Result<()> {
+        if self.keys.is_empty() {
+            return Err(RnmError::SignatureVerificationFailed(
+                "没有可用的公钥，请通过 rnm-rs config set keyring <path> 指定 Node.js 发布团队公钥".to_string(),
+            ));
+        }
+
+        let verified = signatures.iter().any(|signature| {
+            self.keys.iter().any(|key| {
+                signature.verify(key, content).is_ok()
+                    || key
+                        .public_subkeys
+                        .iter()
+                        .any(|subkey| signature.verify(subkey, content).is_ok())
+            })
+        });
+        if verified {
+            Ok(())
+        } else {
+            Err(RnmError::SignatureVerificationFailed(
+                "签名不是由受信任的公钥生成的".to_string(),
+            ))
+        }
+    }
+}
+
+// 逐个解析文本中的 PUBLIC KEY BLOCK，块之外的内容会被忽略
+fn parse_keys(text: &str) -> Result<Vec<SignedPublicKey>> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(KEY_BLOCK_BEGIN) {
+        let end = rest[start..]
+            .find(KEY_BLOCK_END)
+            .map(|end| start + end + KEY_BLOCK_END.len())
+            .ok_or_else(|| RnmError::SignatureVerificationFailed("公钥文件格式错误".to_string()))?;
+
+        let (parsed, _) = SignedPublicKey::from_string_many(&rest[start..end])
+            .map_err(|e| RnmError::SignatureVerificationFailed(format!("无法解析公钥: {}", e)))?;
+        for key in parsed {
+            keys.push(key.map_err(|e| RnmError::SignatureVerificationFailed(format!("无法解析公钥: {}", e)))?);
+        }
+        rest = &rest[end..];
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // tests/fixtures/signature 中的公钥和签名由本地生成的测试密钥创建，与 Node.js 发布团队无关
+    const TRUSTED_KEY: &str = include_str!("../tests/fixtures/signature/trusted.asc");
+    const SHASUMS: &str = include_str!("../tests/fixtures/signature/SHASUMS256.txt");
+    const DETACHED: &[u8] = include_bytes!("../tests/fixtures/signature/SHASUMS256.txt.sig");
+    const DETACHED_UNTRUSTED: &[u8] = include_bytes!("../tests/fixtures/signature/untrusted.sig");
+    const CLEARTEXT: &str = include_str!("../tests/fixtures/signature/SHASUMS256.txt.asc");
+    const CLEARTEXT_UNTRUSTED: &str = include_str!("../tests/fixtures/signature/untrusted.txt.asc");
+
+    fn test_keyring() -> Keyring {
+        Keyring { keys: parse_keys(TRUSTED_KEY).unwrap() }
+    }
+
+    fn assert_rejected(result: Result<()>) {
+        assert!(matches!(result, Err(RnmError::SignatureVerificationFailed(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn detached_signature_from_trusted_key() {
+        test_keyring().verify_detached(SHASUMS.as_bytes(), DETACHED).unwrap();
+    }
+
+    #[test]
+    fn detached_signature_from_untrusted_key() {
+        assert_rejected(test_keyring().verify_detached(SHASUMS.as_bytes(), DETACHED_UNTRUSTED));
+    }
+
+    #[test]
+    fn detached_signature_over_other_content() {
+        let tampered = SHASUMS.replace("a3f0", "0000");
+        assert_rejected(test_keyring().verify_detached(tampered.as_bytes(), DETACHED));
+    }
+
+    #[test]
+    fn cleartext_signature_from_trusted_key() {
+        test_keyring().verify_cleartext(SHASUMS, CLEARTEXT).unwrap();
+    }
+
+    #[test]
+    fn cleartext_signature_from_untrusted_key() {
+        assert_rejected(test_keyring().verify_cleartext(SHASUMS, CLEARTEXT_UNTRUSTED));
+    }
+
+    #[test]
+    fn cleartext_body_differs_from_shasums() {
+        let tampered = SHASUMS.replace("a3f0", "0000");
+        assert_rejected(test_keyring().verify_cleartext(&tampered, CLEARTEXT));
+    }
+
+    #[test]
+    fn extra_keyring_file_is_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.asc");
+        fs::write(&path, TRUSTED_KEY).unwrap();
+        Keyring::load(Some(&path)).unwrap().verify_detached(SHASUMS.as_bytes(), DETACHED).unwrap();
+    }
+
+    // 内置公钥需要联网运行 scripts/update-release-keys.sh 生成，生成并提交后去掉 ignore
+    #[test]
+    #[ignore = "keys/nodejs-release-keys.asc 中还没有公钥，先运行 scripts/update-release-keys.sh"]
+    fn bundled_keyring_is_not_empty() {
+        assert!(!parse_keys(BUNDLED_KEYRING).unwrap().is_empty());
+    }
+
+    #[test]
+    fn empty_keyring_rejects_everything() {
+        let keyring = Keyring { keys: Vec::new() };
+        assert_rejected(keyring.verify_detached(SHASUMS.as_bytes(), DETACHED));
+    }
+}
@@ -1,50 +1,536 @@
 use crate::error::{RnmError, Result};
 use flate2::read::GzDecoder;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use std::fs::{self, File};
-use std::io::{self, Read};
-use std::path::Path;
-use tar::Archive;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use tar::{Archive, EntryType};
+use tempfile::TempDir;
+use xz2::read::XzDecoder;
 
 
 
 // 解压 .tar.gz 文件
 pub fn extract_tar_gz(src: &Path, dst: &Path) -> Result<()> {
-    // 打开源文件
-    let mut file = File::open(src).map_err(|e| RnmError::FileIoError(e))?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).map_err(|e| RnmError::FileIoError(e))?;
-
-    let decoder = GzDecoder::new(&buffer[..]);
-    let mut archive = Archive::new(decoder);
-    archive.unpack(dst).map_err(|e| RnmError::TarGzFileError(e.to_string()))?;
+    let reader = progress_reader(src)?;
+    unpack_tar(GzDecoder::new(reader), dst, RnmError::TarGzFileError)
+}
+
+
+// 解压 .tar.xz 文件
+pub fn extract_tar_xz(src: &Path, dst: &Path) -> Result<()> {
+    let reader = progress_reader(src)?;
+    unpack_tar(XzDecoder::new(reader), dst, RnmError::TarXzFileError)
+}
+
+
+// 边读边解压 tar 流，不会把整个安装包读入内存
+//
+// 每个条目先经过 safe_entry_path 检查，链接的目标也必须留在解压目录内。
+fn unpack_tar<R: Read>(reader: R, dst: &Path, format_error: fn(String) -> RnmError) -> Result<()> {
+    let tar_error = |e: io::Error| format_error(e.to_string());
+    let root = dst.canonicalize()?;
+    let mut archive = Archive::new(reader);
+
+    for entry in archive.entries().map_err(tar_error)? {
+        let mut entry = entry.map_err(tar_error)?;
+        let name = entry.path().map_err(tar_error)?.into_owned();
+        let Some(path) = safe_entry_path(&name)? else {
+            continue;
+        };
+
+        let outpath = create_parent_inside(&root, &path)?;
+        match entry.header().entry_type() {
+            // 符号链接的目标相对于链接所在的目录
+            EntryType::Symlink => {
+                let target = entry.link_name().map_err(tar_error)?.unwrap_or_default();
+                check_link_target(&root, outpath.parent().unwrap_or(&root), &path, &target)?;
+            }
+            // 硬链接的目标相对于解压目录
+            EntryType::Link => {
+                let target = entry.link_name().map_err(tar_error)?.unwrap_or_default();
+                check_link_target(&root, &root, &path, &target)?;
+            }
+            _ => {}
+        }
+
+        entry.unpack_in(&root).map_err(tar_error)?;
+    }
+    Ok(())
+}
+
+
+/// 检查安装包中的条目路径，返回相对于解压目录的路径，`.` 这类空路径返回 None
+///
+/// 绝对路径、Windows 盘符和包含 `..` 的路径都会被拒绝。
+fn safe_entry_path(name: &Path) -> Result<Option<PathBuf>> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(RnmError::UnsafeArchiveEntry(name.display().to_string()));
+            }
+        }
+    }
+    Ok(if path.as_os_str().is_empty() { None } else { Some(path) })
+}
+
+
+// 链接目标从 base 开始逐级解析，经过已解压的符号链接时按实际指向继续，全程不能离开解压目录
+fn check_link_target(root: &Path, base: &Path, link: &Path, target: &Path) -> Result<()> {
+    let escape = || RnmError::UnsafeArchiveEntry(format!("{} -> {}", link.display(), target.display()));
+    let mut current = base.canonicalize()?;
+    for component in target.components() {
+        match component {
+            Component::Normal(part) => {
+                current.push(part);
+                if fs::symlink_metadata(&current).is_ok() {
+                    // 指向不存在位置的链接无法确认最终指向，同样视为不安全
+                    current = current.canonicalize().map_err(|_| escape())?;
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                current.pop();
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(escape()),
+        }
+        if !current.starts_with(root) {
+            return Err(escape());
+        }
+    }
     Ok(())
 }
 
 
+// 创建条目的上级目录，并确认解析已有的符号链接后仍在解压目录内
+fn create_parent_inside(root: &Path, path: &Path) -> Result<PathBuf> {
+    let target = root.join(path);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+        if !parent.canonicalize()?.starts_with(root) {
+            return Err(RnmError::UnsafeArchiveEntry(path.display().to_string()));
+        }
+    }
+    Ok(target)
+}
+
+
+// 打开安装包，并用进度条显示解压进度（按已读取的压缩数据计算）
+fn progress_reader(src: &Path) -> Result<impl Read> {
+    let file = File::open(src).map_err(RnmError::FileIoError)?;
+    let pb = ProgressBar::new(file.metadata()?.len());
+    pb.set_style(extract_style());
+    pb.set_message("Extracting");
+    Ok(pb.wrap_read(BufReader::new(file)))
+}
+
+
+fn extract_style() -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template("{spinner:.green} {msg:10} [{bar:40.cyan/blue}] {bytes}/{total_bytes}")
+        .unwrap()
+        .progress_chars("#>-")
+}
+
+
 // 解压 .zip 文件
 pub fn extract_zip(src: &Path, dst: &Path) -> Result<()> {
-    let mut file = File::open(src).map_err(|e| RnmError::FileIoError(e))?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).map_err(|e| RnmError::FileIoError(e))?;
+    // zip 的目录位于文件末尾，需要可以随机访问的文件而不是流
+    let root = dst.canonicalize()?;
+    let file = File::open(src).map_err(RnmError::FileIoError)?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| RnmError::ZipFileError(e.to_string()))?;
 
-    let reader = std::io::Cursor::new(buffer);
-    let mut archive = zip::ZipArchive::new(reader).map_err(|e| RnmError::ZipFileError(e.to_string()))?;
+    let pb = ProgressBar::new(archive.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg:10} [{bar:40.cyan/blue}] {pos}/{len} files")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    pb.set_message("Extracting");
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i).map_err(|e| RnmError::ZipFileError(e.to_string()))?;
-        let outpath = dst.join(file.name());
+        let Some(path) = safe_entry_path(Path::new(file.name()))? else {
+            pb.inc(1);
+            continue;
+        };
+        let outpath = create_parent_inside(&root, &path)?;
+        let mode = file.unix_mode();
 
-        if file.name().ends_with('/') {
-            fs::create_dir_all(&outpath).map_err(|e| RnmError::FileIoError(e))?;
+        if file.is_dir() {
+            fs::create_dir_all(&outpath).map_err(RnmError::FileIoError)?;
+        } else if mode.is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            // 符号链接条目的内容就是链接目标
+            let mut target = String::new();
+            file.read_to_string(&mut target).map_err(RnmError::FileIoError)?;
+            check_link_target(&root, outpath.parent().unwrap_or(&root), &path, Path::new(&target))?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&target, &outpath).map_err(RnmError::FileIoError)?;
         } else {
-            if let Some(p) = outpath.parent() {
-                fs::create_dir_all(p).map_err(|e| RnmError::FileIoError(e))?;
-            }
-            let mut outfile = File::create(&outpath).map_err(|e| RnmError::FileIoError(e))?;
-            io::copy(&mut file, &mut outfile).map_err(|e| RnmError::FileIoError(e))?;
+            let mut outfile = File::create(&outpath).map_err(RnmError::FileIoError)?;
+            io::copy(&mut file, &mut outfile).map_err(RnmError::FileIoError)?;
+        }
+
+        // 保留 zip 条目中记录的读写和可执行权限，丢弃 setuid、setgid 和 sticky 位
+        #[cfg(unix)]
+        if let Some(mode) = mode.filter(|m| m & S_IFMT != S_IFLNK) {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&outpath, fs::Permissions::from_mode(mode & 0o777))?;
         }
+        pb.inc(1);
     }
+    pb.finish();
     Ok(())
 }
 
 
+// zip 条目 external attributes 高 16 位中的文件类型
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+
+/// 在后台线程中边下载边解压 tar 安装包
+///
+/// 下载到的数据按顺序通过 `feed` 送入解压线程。只要数据出现断档（例如服务器忽略
+/// Range 从头重新发送），就放弃流式解压，由调用方在下载完成后从文件解压。
+pub struct StreamingExtractor {
+    sender: Option<SyncSender<Vec<u8>>>,
+    handle: JoinHandle<Result<()>>,
+    dir: TempDir,
+    fed: u64,
+    multi: MultiProgress,
+    pb: ProgressBar,
+}
+
+impl StreamingExtractor {
+    // 只有 tar 格式支持流式解压，zip 需要随机访问
+    pub fn spawn(extension: &str, dir: TempDir) -> Option<Self> {
+        let decompress: fn(ChannelReader, &Path) -> Result<()> = match extension {
+            "tar.gz" => |reader, dst| unpack_tar(GzDecoder::new(reader), dst, RnmError::TarGzFileError),
+            "tar.xz" => |reader, dst| unpack_tar(XzDecoder::new(reader), dst, RnmError::TarXzFileError),
+            _ => return None,
+        };
+
+        let multi = MultiProgress::new();
+        let pb = multi.add(ProgressBar::new(0));
+        pb.set_style(extract_style());
+        pb.set_message("Extracting");
+
+        let (sender, receiver) = mpsc::sync_channel(256);
+        let reader = ChannelReader { receiver, current: Vec::new(), pos: 0, pb: pb.clone() };
+        let dst = dir.path().to_path_buf();
+        let handle = thread::spawn(move || decompress(reader, &dst));
+
+        Some(Self { sender: Some(sender), handle, dir, fed: 0, multi, pb })
+    }
+
+    /// 下载进度条与解压进度条一起显示
+    pub fn add_progress(&self, pb: ProgressBar) -> ProgressBar {
+        self.multi.insert(0, pb)
+    }
+
+    pub fn set_length(&self, len: u64) {
+        self.pb.set_length(len);
+    }
+
+    /// 送入从 offset 开始的一段数据，与已送入的数据不连续时放弃流式解压
+    pub fn feed(&mut self, offset: u64, chunk: &[u8]) {
+        if offset != self.fed {
+            self.sender = None;
+        }
+        if let Some(sender) = &self.sender {
+            // 解压线程已经出错退出时，send 会失败
+            if sender.send(chunk.to_vec()).is_err() {
+                self.sender = None;
+            }
+            self.fed += chunk.len() as u64;
+        }
+    }
+
+    /// 已经连续送入的字节数
+    pub fn fed(&self) -> u64 {
+        self.fed
+    }
+
+    /// 校验失败或下载出错时丢弃已解压的内容，等待解压线程退出后再删除目录
+    pub fn discard(mut self) {
+        self.sender = None;
+        let _ = self.handle.join();
+        self.pb.finish_and_clear();
+    }
+
+    /// 等待解压完成，流式解压被放弃或失败时返回 None
+    pub fn finish(mut self) -> Option<TempDir> {
+        let complete = self.sender.take().is_some();
+        let result = self.handle.join();
+        if complete && matches!(result, Ok(Ok(()))) {
+            self.pb.finish();
+            Some(self.dir)
+        } else {
+            self.pb.finish_and_clear();
+            None
+        }
+    }
+}
+
+
+// 从 channel 中读取下载数据的 Reader，发送端关闭时视为 EOF
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+    pb: ProgressBar,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.current.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.current = chunk;
+                    self.pos = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.current.len() - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        self.pb.inc(n as u64);
+        Ok(n)
+    }
+}
+
+
+/// 将 bin_dir 加到 PATH 最前面后执行命令，返回命令的退出码
+///
+/// Unix 上直接用命令替换当前进程，信号和退出码都由命令本身处理；其他平台等待子进程结束。
+pub fn exec_in_path(program: &OsStr, args: &[OsString], bin_dir: &Path) -> Result<i32> {
+    let mut paths = vec![bin_dir.to_path_buf()];
+    if let Some(path) = env::var_os("PATH") {
+        paths.extend(env::split_paths(&path));
+    }
+    let path = env::join_paths(paths).map_err(|e| RnmError::FileIoError(io::Error::other(e)))?;
+
+    let mut command = Command::new(program);
+    command.args(args).env("PATH", path);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // exec 只在失败时返回
+        Err(RnmError::FileIoError(command.exec()))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status()?;
+        Ok(status.code().unwrap_or(1))
+    }
+}
+
+
+// 将 file:// 地址转换为本地路径，其他协议返回 None
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    if !url.starts_with("file://") {
+        return None;
+    }
+    reqwest::Url::parse(url).ok()?.to_file_path().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    enum Entry<'a> {
+        Dir(&'a str),
+        File(&'a str, &'a [u8]),
+        Symlink(&'a str, &'a str),
+        Hardlink(&'a str, &'a str),
+    }
+
+    // 直接写入 tar 头中的原始路径，绕过 tar::Builder 对 `..` 和绝对路径的检查
+    fn tar_gz(dir: &Path, entries: &[Entry]) -> PathBuf {
+        let path = dir.join("evil.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(File::create(&path).unwrap(), Compression::fast()));
+        for entry in entries {
+            let (name, link, kind, data): (&str, &str, EntryType, &[u8]) = match *entry {
+                Entry::Dir(name) => (name, "", EntryType::Directory, b""),
+                Entry::File(name, data) => (name, "", EntryType::Regular, data),
+                Entry::Symlink(name, target) => (name, target, EntryType::Symlink, b""),
+                Entry::Hardlink(name, target) => (name, target, EntryType::Link, b""),
+            };
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.as_old_mut().linkname[..link.len()].copy_from_slice(link.as_bytes());
+            header.set_entry_type(kind);
+            header.set_mode(0o755);
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+        path
+    }
+
+    // 解压到 <tmp>/root，返回 (tmp, root)，root 之外的内容用于检查是否逃逸
+    fn setup() -> (TempDir, PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        fs::create_dir(&root).unwrap();
+        (tmp, root)
+    }
+
+    fn assert_unsafe(result: Result<()>) {
+        assert!(matches!(result, Err(RnmError::UnsafeArchiveEntry(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn tar_rejects_parent_dir_entry() {
+        let (tmp, root) = setup();
+        let archive = tar_gz(tmp.path(), &[Entry::File("node/../../evil", b"pwned")]);
+
+        assert_unsafe(extract_tar_gz(&archive, &root));
+        assert!(!tmp.path().join("evil").exists());
+    }
+
+    #[test]
+    fn tar_rejects_absolute_entry() {
+        let (tmp, root) = setup();
+        let outside = tmp.path().join("absolute-evil");
+        let archive = tar_gz(tmp.path(), &[Entry::File(outside.to_str().unwrap(), b"pwned")]);
+
+        assert_unsafe(extract_tar_gz(&archive, &root));
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn tar_rejects_symlink_outside_root() {
+        let (tmp, root) = setup();
+        let archive = tar_gz(tmp.path(), &[Entry::Dir("node"), Entry::Symlink("node/out", "../..")]);
+
+        assert_unsafe(extract_tar_gz(&archive, &root));
+        assert!(fs::symlink_metadata(root.join("node/out")).is_err());
+    }
+
+    #[test]
+    fn tar_rejects_file_written_through_symlink_chain() {
+        let (tmp, root) = setup();
+        // 每个链接单独看都只向上一级，连起来就指向解压目录之外
+        let archive = tar_gz(
+            tmp.path(),
+            &[
+                Entry::Dir("a"),
+                Entry::Symlink("a/l", ".."),
+                Entry::Symlink("a/l/l2", ".."),
+                Entry::File("a/l/l2/evil", b"pwned"),
+            ],
+        );
+
+        assert_unsafe(extract_tar_gz(&archive, &root));
+        assert!(!tmp.path().join("evil").exists());
+    }
+
+    #[test]
+    fn tar_rejects_hardlink_outside_root() {
+        let (tmp, root) = setup();
+        fs::write(tmp.path().join("secret"), b"secret").unwrap();
+        let archive = tar_gz(tmp.path(), &[Entry::Dir("node"), Entry::Hardlink("node/h", "../secret")]);
+
+        assert_unsafe(extract_tar_gz(&archive, &root));
+        assert!(!root.join("node/h").exists());
+    }
+
+    #[test]
+    fn tar_extracts_links_inside_root() {
+        let (tmp, root) = setup();
+        let archive = tar_gz(
+            tmp.path(),
+            &[
+                Entry::Dir("node/bin"),
+                Entry::File("node/lib/cli.js", b"cli"),
+                Entry::Symlink("node/bin/npm", "../lib/cli.js"),
+                Entry::Hardlink("node/bin/npm-hard", "node/lib/cli.js"),
+            ],
+        );
+
+        extract_tar_gz(&archive, &root).unwrap();
+        assert_eq!(fs::read(root.join("node/bin/npm")).unwrap(), b"cli");
+        assert_eq!(fs::read(root.join("node/bin/npm-hard")).unwrap(), b"cli");
+    }
+
+    fn zip_archive(dir: &Path, build: impl FnOnce(&mut zip::ZipWriter<File>)) -> PathBuf {
+        let path = dir.join("node.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        build(&mut writer);
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn zip_rejects_parent_dir_entry() {
+        let (tmp, root) = setup();
+        let archive = zip_archive(tmp.path(), |zip| {
+            zip.start_file("node/../../evil", zip::write::FileOptions::default()).unwrap();
+            zip.write_all(b"pwned").unwrap();
+        });
+
+        assert_unsafe(extract_zip(&archive, &root));
+        assert!(!tmp.path().join("evil").exists());
+    }
+
+    #[test]
+    fn zip_rejects_symlink_outside_root() {
+        let (tmp, root) = setup();
+        let archive = zip_archive(tmp.path(), |zip| {
+            zip.add_symlink("node/out", "../..", zip::write::FileOptions::default()).unwrap();
+        });
+
+        assert_unsafe(extract_zip(&archive, &root));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zip_keeps_unix_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (tmp, root) = setup();
+        let archive = zip_archive(tmp.path(), |zip| {
+            let options = zip::write::FileOptions::default().unix_permissions(0o755);
+            zip.start_file("node/bin/node", options).unwrap();
+            zip.write_all(b"#!/bin/sh\n").unwrap();
+        });
+
+        extract_zip(&archive, &root).unwrap();
+        let mode = fs::metadata(root.join("node/bin/node")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zip_drops_setuid_bits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (tmp, root) = setup();
+        let archive = zip_archive(tmp.path(), |zip| {
+            let options = zip::write::FileOptions::default().unix_permissions(0o6755);
+            zip.start_file("node/bin/node", options).unwrap();
+            zip.write_all(b"#!/bin/sh\n").unwrap();
+        });
+
+        extract_zip(&archive, &root).unwrap();
+        let mode = fs::metadata(root.join("node/bin/node")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o755);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::error::{Result, RnmError};
+use crate::remote::RemoteVersion;
+use semver::{Version, VersionReq};
+use std::str::FromStr;
+
+/// 用户输入的版本描述，例如 `20.11.1`、`18`、`^18.12`、`latest`、`lts/iron`、`lts/-1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSpec {
+    // latest / node: 最新发布的版本
+    Latest,
+    // lts / lts/*: 最新的 LTS 版本
+    Lts,
+    // lts/<codename>: 指定代号的 LTS 版本线
+    LtsCodename(String),
+    // lts/-n: 从最新的 LTS 版本线往前数第 n 条
+    LtsOffset(usize),
+    Exact(Version),
+    // 部分版本号（18、18.19）或 semver 范围（^18.12、>=16 <20），多个范围之间为 || 关系
+    Range(Vec<VersionReq>),
+}
+
+impl FromStr for VersionSpec {
+    type Err = RnmError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let input = s.trim();
+        let lower = input.to_ascii_lowercase();
+
+        match lower.as_str() {
+            "latest" | "node" => return Ok(Self::Latest),
+            "lts" | "lts/*" => return Ok(Self::Lts),
+            _ => {}
+        }
+
+        if let Some(rest) = lower.strip_prefix("lts/") {
+            if let Some(offset) = rest.strip_prefix('-') {
+                let offset = offset
+                    .parse::<usize>()
+                    .map_err(|_| RnmError::InvalidNodeVersion(input.to_string()))?;
+                return Ok(Self::LtsOffset(offset));
+            }
+            if rest.is_empty() {
+                return Err(RnmError::InvalidNodeVersion(input.to_string()));
+            }
+            return Ok(Self::LtsCodename(rest.to_string()));
+        }
+
+        if let Ok(version) = Version::parse(input.trim_start_matches('v')) {
+            return Ok(Self::Exact(version));
+        }
+
+        parse_range(input)
+            .map(Self::Range)
+            .ok_or_else(|| RnmError::InvalidNodeVersion(input.to_string()))
+    }
+}
+
+// 将 npm 风格的版本范围转换为 semver crate 可以识别的形式
+fn parse_range(input: &str) -> Option<Vec<VersionReq>> {
+    input
+        .split("||")
+        .map(|part| {
+            let part = part.trim();
+            if part.is_empty() {
+                return None;
+            }
+
+            // 部分版本号只匹配该版本线，例如 18.19 只匹配 18.19.x
+            let bare = part.trim_start_matches('v');
+            if bare.split('.').all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit())) {
+                return VersionReq::parse(&format!("={}", bare)).ok();
+            }
+
+            // 连字符范围: 16 - 18.2 等价于 >=16, <=18.2
+            if let Some((low, high)) = part.split_once(" - ") {
+                let req = format!(
+                    ">={}, <={}",
+                    low.trim().trim_start_matches('v'),
+                    high.trim().trim_start_matches('v')
+                );
+                return VersionReq::parse(&req).ok();
+            }
+
+            // 空格分隔的多个比较条件，兼容运算符与版本号之间带空格的写法，例如 ">= 16 < 20"
+            let mut comparators: Vec<String> = Vec::new();
+            let mut pending_op = String::new();
+            for token in part.split(|c: char| c.is_whitespace() || c == ',') {
+                if token.is_empty() {
+                    continue;
+                }
+                if token.chars().all(|c| "<>=~^".contains(c)) {
+                    pending_op.push_str(token);
+                    continue;
+                }
+                let op_len = token.find(|c: char| !"<>=~^".contains(c)).unwrap_or(token.len());
+                let (op, version) = token.split_at(op_len);
+                comparators.push(format!("{}{}{}", pending_op, op, version.trim_start_matches('v')));
+                pending_op.clear();
+            }
+            if !pending_op.is_empty() || comparators.is_empty() {
+                return None;
+            }
+            VersionReq::parse(&comparators.join(", ")).ok()
+        })
+        .collect()
+}
+
+impl VersionSpec {
+    /// 根据 index.json 中的发布记录解析出具体版本，`remote` 需按从新到旧排序
+    pub fn resolve_remote<'a>(&self, remote: &'a [RemoteVersion]) -> Option<&'a RemoteVersion> {
+        match self {
+            Self::Latest => remote.first(),
+            Self::Exact(version) => remote.iter().find(|r| &r.version == version),
+            Self::Range(reqs) => remote.iter().find(|r| matches_any(reqs, &r.version)),
+            _ => {
+                let codename = self.lts_codename(remote)?;
+                remote
+                    .iter()
+                    .find(|r| r.lts.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(&codename)))
+            }
+        }
+    }
+
+    /// 在已安装的版本中选出满足描述的最新版本
+    ///
+    /// LTS 相关的描述需要借助远程索引判断版本所属的版本线。
+    pub fn resolve_installed(&self, installed: &[Version], remote: &[RemoteVersion]) -> Option<Version> {
+        match self {
+            Self::Latest => installed.iter().max().cloned(),
+            Self::Exact(version) => installed.iter().find(|v| *v == version).cloned(),
+            Self::Range(reqs) => installed.iter().filter(|v| matches_any(reqs, v)).max().cloned(),
+            _ => {
+                let codename = self.lts_codename(remote)?;
+                installed
+                    .iter()
+                    .filter(|v| {
+                        remote.iter().any(|r| {
+                            &r.version == *v
+                                && r.lts.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(&codename))
+                        })
+                    })
+                    .max()
+                    .cloned()
+            }
+        }
+    }
+
+    /// 该描述是否需要远程索引中的 LTS 信息才能解析
+    pub fn needs_lts_info(&self) -> bool {
+        matches!(self, Self::Lts | Self::LtsCodename(_) | Self::LtsOffset(_))
+    }
+
+    // 将 LTS 类描述统一转换为版本线代号
+    fn lts_codename(&self, remote: &[RemoteVersion]) -> Option<String> {
+        let mut codenames: Vec<&str> = Vec::new();
+        for r in remote {
+            if let Some(codename) = r.lts.as_deref() {
+                if !codenames.iter().any(|c| c.eq_ignore_ascii_case(codename)) {
+                    codenames.push(codename);
+                }
+            }
+        }
+
+        match self {
+            Self::Lts => codenames.first().map(|c| c.to_string()),
+            Self::LtsOffset(offset) => codenames.get(*offset).map(|c| c.to_string()),
+            Self::LtsCodename(codename) => Some(codename.clone()),
+            _ => None,
+        }
+    }
+}
+
+fn matches_any(reqs: &[VersionReq], version: &Version) -> bool {
+    reqs.iter().any(|req| req.matches(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 解析为范围后检查各个版本是否匹配
+    fn assert_range(spec: &str, matching: &[&str], not_matching: &[&str]) {
+        let parsed: VersionSpec = spec.parse().unwrap();
+        let VersionSpec::Range(reqs) = parsed else {
+            panic!("{} 没有解析为范围: {:?}", spec, parsed);
+        };
+        for version in matching {
+            assert!(matches_any(&reqs, &Version::parse(version).unwrap()), "{} 应匹配 {}", spec, version);
+        }
+        for version in not_matching {
+            assert!(!matches_any(&reqs, &Version::parse(version).unwrap()), "{} 不应匹配 {}", spec, version);
+        }
+    }
+
+    #[test]
+    fn space_separated_comparators() {
+        assert_range(">=16 <20", &["16.0.0", "19.9.9"], &["15.9.9", "20.0.0"]);
+    }
+
+    #[test]
+    fn operators_separated_from_versions() {
+        assert_range(">= 16 < 20", &["16.0.0", "19.9.9"], &["15.9.9", "20.0.0"]);
+    }
+
+    #[test]
+    fn hyphen_range() {
+        assert_range("16 - 18", &["16.0.0", "18.20.1"], &["15.9.9", "19.0.0"]);
+    }
+
+    #[test]
+    fn or_ranges() {
+        assert_range("^18 || ^20", &["18.19.0", "20.11.1"], &["16.20.0", "19.0.0", "22.1.0"]);
+    }
+
+    #[test]
+    fn wildcard_and_partial_versions() {
+        assert_range("18.x", &["18.0.0", "18.20.1"], &["17.9.9", "19.0.0"]);
+        assert_range("18", &["18.20.1"], &["19.0.0"]);
+        assert_range("v18.19", &["18.19.1"], &["18.20.0"]);
+    }
+
+    #[test]
+    fn exact_and_keywords() {
+        assert_eq!("v20.11.1".parse::<VersionSpec>().unwrap(), VersionSpec::Exact(Version::new(20, 11, 1)));
+        assert_eq!("node".parse::<VersionSpec>().unwrap(), VersionSpec::Latest);
+        assert_eq!("lts/*".parse::<VersionSpec>().unwrap(), VersionSpec::Lts);
+        assert_eq!("lts/Iron".parse::<VersionSpec>().unwrap(), VersionSpec::LtsCodename("iron".to_string()));
+        assert_eq!("lts/-1".parse::<VersionSpec>().unwrap(), VersionSpec::LtsOffset(1));
+    }
+
+    #[test]
+    fn invalid_input() {
+        for input in ["", "abc", ">=", "^18 ||", "16 -", "lts/", "lts/-x", "current"] {
+            assert!(
+                matches!(input.parse::<VersionSpec>(), Err(RnmError::InvalidNodeVersion(_))),
+                "{:?} 应该是无效的版本描述",
+                input
+            );
+        }
+    }
+}